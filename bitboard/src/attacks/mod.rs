@@ -0,0 +1,91 @@
+use crate::patterns::knight;
+use crate::{east_one, no_east_one, no_west_one, north_one, so_east_one, so_west_one, south_one, west_one};
+
+pub use crate::magic::{bishop_attacks, queen_attacks, rook_attacks};
+
+pub const KNIGHT_ATTACKS: [u64; 64] = make_knight_attacks();
+pub const KING_ATTACKS: [u64; 64] = make_king_attacks();
+// Indexed by the color of the pawn, white first.
+pub const PAWN_ATTACKS: [[u64; 64]; 2] = make_pawn_attacks();
+
+const fn make_knight_attacks() -> [u64; 64] {
+    let mut result = [0u64; 64];
+    let mut sq = 0;
+
+    while sq < 64 {
+        let bb: u64 = 1 << sq;
+
+        result[sq] = knight::no_no_ea(bb) | knight::no_ea_ea(bb)
+            | knight::so_ea_ea(bb) | knight::so_so_ea(bb)
+            | knight::no_no_we(bb) | knight::no_we_we(bb)
+            | knight::so_we_we(bb) | knight::so_so_we(bb);
+        sq += 1;
+    }
+
+    result
+}
+
+const fn make_king_attacks() -> [u64; 64] {
+    let mut result = [0u64; 64];
+    let mut sq = 0;
+
+    while sq < 64 {
+        let bb: u64 = 1 << sq;
+
+        result[sq] = north_one(bb) | no_east_one(bb) | east_one(bb) | so_east_one(bb)
+            | south_one(bb) | so_west_one(bb) | west_one(bb) | no_west_one(bb);
+        sq += 1;
+    }
+
+    result
+}
+
+const fn make_pawn_attacks() -> [[u64; 64]; 2] {
+    let mut result = [[0u64; 64]; 2];
+    let mut sq = 0;
+
+    while sq < 64 {
+        let bb: u64 = 1 << sq;
+
+        result[0][sq] = no_west_one(bb) | no_east_one(bb);
+        result[1][sq] = so_west_one(bb) | so_east_one(bb);
+        sq += 1;
+    }
+
+    result
+}
+
+pub fn knight_attacks(square: usize) -> u64 {
+    KNIGHT_ATTACKS[square]
+}
+
+pub fn king_attacks(square: usize) -> u64 {
+    KING_ATTACKS[square]
+}
+
+pub fn pawn_attacks(color: usize, square: usize) -> u64 {
+    PAWN_ATTACKS[color][square]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leaper_attacks() {
+        // a1, e4 and h8
+        assert_eq!(knight_attacks(0), 0x20400);
+        assert_eq!(knight_attacks(28).count_ones(), 8);
+        assert_eq!(knight_attacks(63), 0x20400000000000);
+
+        assert_eq!(king_attacks(0), 0x302);
+        assert_eq!(king_attacks(28).count_ones(), 8);
+        assert_eq!(king_attacks(63), 0x40c0000000000000);
+
+        assert_eq!(pawn_attacks(0, 28), 0x28_0000_0000);
+        assert_eq!(pawn_attacks(1, 28), 0x28_0000);
+        assert_eq!(pawn_attacks(0, 8), 0x20000);
+        assert_eq!(pawn_attacks(1, 15), 0x40);
+        assert_eq!(pawn_attacks(0, 63), 0);
+    }
+}
@@ -1,7 +1,13 @@
+pub mod attacks;
+pub mod magic;
 pub mod patterns;
 
 use utils::{file, direction::{NORT, EAST, NOEA, NOWE}};
 
+// a1 is a dark square.
+pub const LIGHT_SQUARES: u64 = 0x55aa_55aa_55aa_55aa;
+pub const DARK_SQUARES: u64 = !LIGHT_SQUARES;
+
 pub fn is_empty(bb: u64) -> bool {
     bb == 0
 }
@@ -20,39 +26,50 @@ pub fn flip_vertical(bb: u64) -> u64 {
     bb.swap_bytes()
 }
 
+// Mirror files a-h, keeping ranks.
+pub fn mirror_horizontal(bb: u64) -> u64 {
+    const K1: u64 = 0x5555_5555_5555_5555;
+    const K2: u64 = 0x3333_3333_3333_3333;
+    const K4: u64 = 0x0f0f_0f0f_0f0f_0f0f;
+
+    let bb = ((bb >> 1) & K1) | ((bb & K1) << 1);
+    let bb = ((bb >> 2) & K2) | ((bb & K2) << 2);
+    ((bb >> 4) & K4) | ((bb & K4) << 4)
+}
+
 pub fn count_bits(bb: u64) -> u32 {
     bb.count_ones()
 }
 
-pub fn south_one(b: u64) -> u64 {
+pub const fn south_one(b: u64) -> u64 {
     b >> NORT
 }
 
-pub fn north_one(b: u64) -> u64 {
+pub const fn north_one(b: u64) -> u64 {
     b << NORT
 }
 
-pub fn east_one(b: u64) -> u64 {
+pub const fn east_one(b: u64) -> u64 {
     (b << EAST) & file::NOT_A_FILE
 }
 
-pub fn no_east_one(b: u64) -> u64 {
+pub const fn no_east_one(b: u64) -> u64 {
     (b << NOEA) & file::NOT_A_FILE
 }
 
-pub fn so_east_one(b: u64) -> u64 {
+pub const fn so_east_one(b: u64) -> u64 {
     (b >> NOWE) & file::NOT_A_FILE
 }
 
-pub fn west_one(b: u64) -> u64 {
+pub const fn west_one(b: u64) -> u64 {
     (b >> EAST) & file::NOT_H_FILE
 }
 
-pub fn no_west_one(b: u64) -> u64 {
+pub const fn no_west_one(b: u64) -> u64 {
     (b << NOWE) & file::NOT_H_FILE
 }
 
-pub fn so_west_one(b: u64) -> u64 {
+pub const fn so_west_one(b: u64) -> u64 {
     (b >> NOEA) & file::NOT_H_FILE
 }
 
@@ -60,6 +77,15 @@ pub fn so_west_one(b: u64) -> u64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_square_colors() {
+        assert_eq!(DARK_SQUARES & 1, 1); // a1
+        assert_eq!(LIGHT_SQUARES & 1 << 3, 1 << 3); // d1
+        assert_eq!(LIGHT_SQUARES & 1 << 7, 1 << 7); // h1
+        assert_eq!(DARK_SQUARES & 1 << 63, 1 << 63); // h8
+        assert_eq!(LIGHT_SQUARES.count_ones(), 32);
+    }
+
     #[test]
     fn test_is_empty() {
         assert!(is_empty(0));
@@ -90,5 +116,15 @@ mod tests {
                 0b01000100_01001000_01010000_01110000_01001000_01000100_01000100_01111000
         );
     }
+
+    #[test]
+    fn test_mirror_horizontal() {
+        assert_eq!(mirror_horizontal(0b00000001), 0b10000000);
+        assert_eq!(
+            mirror_horizontal(0b00000001_00000011_00000111_00001111_00010000_00100000_01000000_10000000),
+            0b10000000_11000000_11100000_11110000_00001000_00000100_00000010_00000001
+        );
+        assert_eq!(mirror_horizontal(mirror_horizontal(0x0123_4567_89ab_cdef)), 0x0123_4567_89ab_cdef);
+    }
 }
 
@@ -0,0 +1,260 @@
+use lazy_static::lazy_static;
+
+#[cfg(all(feature = "pext", not(target_arch = "x86_64")))]
+compile_error!("The pext feature needs an x86-64 target");
+
+// Rustdoc does not get RUSTFLAGS when it collects the doctests, which link
+// against the library built with them.
+#[cfg(all(feature = "pext", target_arch = "x86_64", not(target_feature = "bmi2"), not(doctest)))]
+compile_error!("The pext feature needs BMI2, build with RUSTFLAGS=\"-C target-feature=+bmi2\"");
+
+use utils::file::FILES;
+use utils::rank::RANKS;
+
+// (file, rank) steps of the sliding pieces.
+pub const ROOK_DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+pub const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (-1, 1), (1, -1), (-1, -1)];
+
+// One seed per rank, chosen so that magics are found after few tries.
+const SEEDS: [u64; 8] = [728, 10316, 55013, 32803, 12281, 15100, 16645, 255];
+
+lazy_static! {
+    pub static ref ROOK_MAGICS: MagicTable = MagicTable::new(&ROOK_DIRECTIONS);
+    pub static ref BISHOP_MAGICS: MagicTable = MagicTable::new(&BISHOP_DIRECTIONS);
+}
+
+// Multiplying the relevant occupancy by the magic gathers its bits in the
+// top `64 - shift` bits, giving the index of the attacks in the table. With
+// the pext feature the bits are gathered by the instruction and the magic is
+// not used.
+pub struct Magic {
+    pub mask: u64,
+    pub magic: u64,
+    pub shift: u32,
+    pub offset: usize,
+}
+
+impl Magic {
+    #[cfg(not(feature = "pext"))]
+    pub fn index(&self, occupancy: u64) -> usize {
+        self.offset + ((occupancy & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+
+    #[cfg(feature = "pext")]
+    pub fn index(&self, occupancy: u64) -> usize {
+        self.offset + pext(occupancy, self.mask) as usize
+    }
+}
+
+// BMI2 is enabled for the whole build, so the instruction is inlined.
+#[cfg(feature = "pext")]
+#[inline]
+fn pext(bb: u64, mask: u64) -> u64 {
+    // SAFETY: the crate does not build without the bmi2 target feature.
+    unsafe { std::arch::x86_64::_pext_u64(bb, mask) }
+}
+
+pub struct MagicTable {
+    pub magics: Vec<Magic>,
+    pub attacks: Vec<u64>,
+}
+
+// xorshift64*, enough to draw magic candidates.
+#[cfg_attr(feature = "pext", allow(dead_code))]
+struct Prng(u64);
+
+#[cfg_attr(feature = "pext", allow(dead_code))]
+impl Prng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(2685821657736338717)
+    }
+
+    // Few bits set, good magics usually are.
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+// Squares reached from `square` in each direction, up to the edge or the
+// first occupied square included. Slow, used to fill and check the tables.
+pub fn sliding_attacks(square: usize, occupancy: u64, directions: &[(i32, i32)]) -> u64 {
+    let mut attacks = 0;
+
+    for (file_step, rank_step) in directions {
+        let mut file = (square % 8) as i32 + file_step;
+        let mut rank = (square / 8) as i32 + rank_step;
+
+        while (0..8).contains(&file) && (0..8).contains(&rank) {
+            let bb = 1u64 << (rank * 8 + file);
+            attacks |= bb;
+
+            if occupancy & bb != 0 {
+                break;
+            }
+
+            file += file_step;
+            rank += rank_step;
+        }
+    }
+
+    attacks
+}
+
+// Every subset of `mask`, empty set included.
+fn subsets(mask: u64) -> Vec<u64> {
+    let mut result = Vec::with_capacity(1 << mask.count_ones());
+    let mut subset: u64 = 0;
+
+    loop {
+        result.push(subset);
+        subset = subset.wrapping_sub(mask) & mask;
+
+        if subset == 0 {
+            return result;
+        }
+    }
+}
+
+#[cfg(not(feature = "pext"))]
+fn fill_attacks(magic: &mut Magic, occupancies: &[u64], reference: &[u64], attacks: &mut [u64], seed: u64) {
+    // Entries filled by an earlier try are recognized by their epoch
+    // instead of clearing the table each time.
+    let mut rng = Prng(seed);
+    let mut epoch = vec![0u32; occupancies.len()];
+    let mut attempt = 0;
+
+    'search: loop {
+        magic.magic = rng.sparse();
+        if (magic.magic.wrapping_mul(magic.mask) >> 56).count_ones() < 6 {
+            continue;
+        }
+
+        attempt += 1;
+        for (occupancy, reference) in occupancies.iter().zip(reference.iter()) {
+            let index = magic.index(*occupancy);
+
+            if epoch[index - magic.offset] < attempt {
+                epoch[index - magic.offset] = attempt;
+                attacks[index] = *reference;
+            } else if attacks[index] != *reference {
+                continue 'search;
+            }
+        }
+
+        return;
+    }
+}
+
+#[cfg(feature = "pext")]
+fn fill_attacks(magic: &mut Magic, occupancies: &[u64], reference: &[u64], attacks: &mut [u64], _seed: u64) {
+    for (occupancy, reference) in occupancies.iter().zip(reference.iter()) {
+        attacks[magic.index(*occupancy)] = *reference;
+    }
+}
+
+impl MagicTable {
+    fn new(directions: &[(i32, i32)]) -> MagicTable {
+        #[cfg(feature = "pext")]
+        assert!(is_x86_feature_detected!("bmi2"), "This build needs a CPU with BMI2");
+
+        let mut magics = Vec::with_capacity(64);
+        let mut attacks = Vec::new();
+
+        for square in 0..64 {
+            // Pieces on the edge of a ray never change the attacks, unless
+            // the slider stands on that edge itself.
+            let edges = ((RANKS[0] | RANKS[7]) & !RANKS[square / 8])
+                | ((FILES[0] | FILES[7]) & !FILES[square % 8]);
+            let mask = sliding_attacks(square, 0, directions) & !edges;
+            let bits = mask.count_ones();
+
+            let occupancies = subsets(mask);
+            let reference: Vec<u64> = occupancies.iter()
+                .map(|occupancy| sliding_attacks(square, *occupancy, directions))
+                .collect();
+
+            let mut magic = Magic { mask, magic: 0, shift: 64 - bits, offset: attacks.len() };
+            attacks.resize(attacks.len() + occupancies.len(), 0);
+
+            fill_attacks(&mut magic, &occupancies, &reference, &mut attacks, SEEDS[square / 8]);
+            magics.push(magic);
+        }
+
+        let table = MagicTable { magics, attacks };
+        assert!(table.self_test(directions), "Magic table does not match the ray walk");
+
+        table
+    }
+
+    pub fn attacks(&self, square: usize, occupancy: u64) -> u64 {
+        self.attacks[self.magics[square].index(occupancy)]
+    }
+
+    // Compare every entry of the table with the ray walk.
+    pub fn self_test(&self, directions: &[(i32, i32)]) -> bool {
+        (0..64).all(|square| {
+            subsets(self.magics[square].mask).iter().all(|occupancy| {
+                self.attacks(square, *occupancy) == sliding_attacks(square, *occupancy, directions)
+            })
+        })
+    }
+}
+
+pub fn rook_attacks(square: usize, occupancy: u64) -> u64 {
+    ROOK_MAGICS.attacks(square, occupancy)
+}
+
+pub fn bishop_attacks(square: usize, occupancy: u64) -> u64 {
+    BISHOP_MAGICS.attacks(square, occupancy)
+}
+
+pub fn queen_attacks(square: usize, occupancy: u64) -> u64 {
+    rook_attacks(square, occupancy) | bishop_attacks(square, occupancy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliding_attacks() {
+        assert_eq!(sliding_attacks(0, 0, &ROOK_DIRECTIONS), 0x1010101010101fe);
+        assert_eq!(sliding_attacks(20, 0, &ROOK_DIRECTIONS), 0x1010101010ef1010);
+        assert_eq!(sliding_attacks(20, 0x80000, &ROOK_DIRECTIONS), 0x1010101010e81010);
+        assert_eq!(sliding_attacks(0, 0, &BISHOP_DIRECTIONS), 0x8040201008040200);
+        assert_eq!(sliding_attacks(59, 0x20000000000, &BISHOP_DIRECTIONS), 0x14224080000000);
+    }
+
+    #[test]
+    fn test_magic_tables() {
+        assert!(ROOK_MAGICS.self_test(&ROOK_DIRECTIONS));
+        assert!(BISHOP_MAGICS.self_test(&BISHOP_DIRECTIONS));
+
+    }
+
+    // Whichever way the tables are indexed, they give the ray walk results.
+    // Pieces outside of the mask must not change the attacks either.
+    #[test]
+    fn test_random_occupancies() {
+        let mut rng = Prng(0x5eed);
+        for square in 0..64 {
+            for _ in 0..100 {
+                let occupancy = rng.next() & rng.next();
+                assert_eq!(rook_attacks(square, occupancy), sliding_attacks(square, occupancy, &ROOK_DIRECTIONS));
+                assert_eq!(bishop_attacks(square, occupancy), sliding_attacks(square, occupancy, &BISHOP_DIRECTIONS));
+                assert_eq!(queen_attacks(square, occupancy), rook_attacks(square, occupancy) | bishop_attacks(square, occupancy));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "pext")]
+    fn test_pext() {
+        assert_eq!(pext(0b1011_0110, 0b1111_0000), 0b1011);
+        assert_eq!(pext(0x8000_0000_0000_0001, 0x8000_0000_0000_0001), 0b11);
+        assert_eq!(pext(u64::MAX, 0), 0);
+    }
+}
@@ -34,3 +34,26 @@ pub fn piece_value(piece: &Piece) -> i32 {
         Piece::King => 2000,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material_balance_symmetry() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/QQQ1K3 b - - 0 1",
+        ];
+
+        for fen in fens {
+            let chessboard = Chessboard::new(fen.to_string());
+            let mirrored = chessboard.mirrored();
+            assert_eq!(material_balance(&chessboard, &Color::White), material_balance(&mirrored, &Color::Black), "{}", fen);
+            assert_eq!(material_balance(&chessboard, &Color::Black), material_balance(&mirrored, &Color::White), "{}", fen);
+        }
+    }
+}
@@ -2,33 +2,27 @@ pub mod basic;
 
 use utils::color::Color;
 
+use crate::chessboard::outcome::Outcome;
+
 use self::basic::material::material_balance;
 
 use super::Ai;
 
+// Symmetric, so that negating a score never overflows.
 pub const MAX_SCORE: i32 = std::i32::MAX;
-pub const MIN_SCORE: i32 = std::i32::MIN;
+pub const MIN_SCORE: i32 = -MAX_SCORE;
 
 impl Ai {
     pub fn evaluate_board(&mut self, turn: &Color) -> i32 {
-        if self.is_end_game {
-            self.is_end_game = false;
-            //println!("End game: {:?}", turn);
-            //self.chessboard.pretty_print();
+        material_balance(&self.chessboard, turn)
+    }
 
-            if self.chessboard.is_in_check(turn) {
-                //println!("Checkmate: {:?}", self.color);
-                if *turn == self.color {
-                    return MIN_SCORE;
-                } else {
-                    return MAX_SCORE;
-                }
-            }
-            else if *turn == self.color {
-                return 0;
-            }
+    // Score of a finished game, from the point of view of the AI.
+    pub fn evaluate_outcome(&self, outcome: &Outcome) -> i32 {
+        match outcome.winner() {
+            Some(winner) if winner == self.color => MAX_SCORE,
+            Some(_) => MIN_SCORE,
+            None => 0,
         }
-
-        material_balance(&self.chessboard, turn)
     }
 }
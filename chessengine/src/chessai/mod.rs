@@ -1,6 +1,6 @@
 use utils::color::Color;
 
-use crate::chessboard::{Chessboard, moves::Move};
+use crate::chessboard::{Chessboard, moves::Move, outcome::MaterialRule};
 
 pub mod evaluation;
 mod search;
@@ -9,7 +9,8 @@ mod uci;
 pub struct Ai {
     pub chessboard: Chessboard,
     pub color: Color,
-    pub is_end_game: bool,
+    pub chess960: bool,
+    pub material_rule: MaterialRule,
 }
 
 impl Ai {
@@ -19,7 +20,8 @@ impl Ai {
         Ai {
             chessboard,
             color,
-            is_end_game: false,
+            chess960: false,
+            material_rule: MaterialRule::Fide,
         }
     }
 }
@@ -1,19 +1,19 @@
 use crate::chessai::{Ai, evaluation::{MIN_SCORE, MAX_SCORE}};
+use crate::chessboard::moves::GenType;
 
 impl Ai {
     pub fn alpha_beta(&mut self,
                       depth: u32,
                       mut alpha: i32,
                       mut beta: i32) -> i32 {
-        let moves = self.chessboard.generate_legal_moves();
+        let moves = self.chessboard.generate_legal_moves(GenType::All);
         let color = self.chessboard.turn;
 
-        if moves.len() == 0 {
-            //self.chessboard.pretty_print();
-            self.is_end_game = true;
+        if let Some(outcome) = self.chessboard.outcome_with_legal_moves(&moves) {
+            return self.evaluate_outcome(&outcome);
         }
 
-        if depth == 0 || self.is_end_game {
+        if depth == 0 {
             return self.evaluate_board(&color);
         }
 
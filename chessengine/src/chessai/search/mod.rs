@@ -1,6 +1,6 @@
 use utils::piece::Piece;
 
-use crate::chessboard::{moves::Move, Chessboard};
+use crate::chessboard::{moves::{list::MoveList, GenType, Move}, Chessboard};
 
 use super::{Ai, evaluation::{MIN_SCORE, MAX_SCORE, basic::material::piece_value}};
 
@@ -12,7 +12,7 @@ impl Ai {
         let mut best_move: Option<Move> = None;
         let mut best_move_score = MIN_SCORE;
 
-        let mut moves = self.chessboard.generate_legal_moves();
+        let mut moves = self.chessboard.generate_legal_moves(GenType::All);
         self.chessboard.order_moves(&mut moves);
 
         for mv in moves {
@@ -33,19 +33,24 @@ impl Ai {
 }
 
 impl Chessboard {
-    pub fn order_moves(&mut self, moves: &mut Vec<Move>) {
-        moves.sort_by(|a, b| self.see(b).cmp(&self.see(a)));
+    pub fn order_moves(&mut self, moves: &mut MoveList) {
+        for i in 0..moves.len() {
+            let score = self.see(&moves[i]);
+            moves.set_score(i, score);
+        }
+
+        moves.sort_by_score();
     }
 
     pub fn see(&mut self, mv: &Move) -> i32 {
-        if mv.capture == None || mv.promotion == None {
+        if !mv.is_capture() || !mv.is_promotion() {
             return 0;
         }
 
-        let piece: Piece = if mv.promotion != None {
-            Piece::from(mv.promotion.unwrap())
+        let piece: Piece = if mv.is_promotion() {
+            mv.promotion().unwrap()
         } else {
-            self.get_piece(&mv.from).unwrap()
+            self.get_piece(&mv.from()).unwrap()
         };
 
         let value = piece_value(&piece);
@@ -53,9 +58,10 @@ impl Chessboard {
 
         self.make_move(mv);
 
-        let opponents_moves = self.generate_legal_moves();
+        // Taking back on the square is always a capture.
+        let opponents_moves = self.generate_legal_moves(GenType::Captures);
         for opponent_move in opponents_moves {
-            if opponent_move.to == mv.to {
+            if opponent_move.to() == mv.to() {
                 let opponent_response = -self.see(&opponent_move);
                 opponent_responses.push(opponent_response);
             }
@@ -71,6 +77,8 @@ impl Chessboard {
 
 #[cfg(test)]
 mod tests {
+    use utils::color::Color;
+
     use super::*;
 
     fn test_find_best_move(fen: &str, expected: &str) {
@@ -80,6 +88,14 @@ mod tests {
         assert_eq!(best_move, expected_best_move);
     }
 
+    #[test]
+    fn test_negamax_lost_outcome() {
+        // White is mated, the loss of the AI seen from the other side.
+        let mut chessai = Ai::new("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string());
+        assert_eq!(chessai.negamax(&Color::Black, 0), MAX_SCORE);
+        assert_eq!(chessai.negamax(&Color::White, 0), MIN_SCORE);
+    }
+
     #[test]
     #[ignore]
     fn test_negamax() {
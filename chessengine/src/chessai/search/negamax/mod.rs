@@ -1,14 +1,15 @@
 use utils::color::Color;
 
 use crate::chessai::Ai;
+use crate::chessboard::moves::GenType;
 
 impl Ai {
     pub fn negamax(&mut self, color_to_max: &Color, depth: u32) -> i32 {
         let modifier = if self.chessboard.turn == *color_to_max { 1 } else { -1 };
-        let moves = self.chessboard.generate_legal_moves();
+        let moves = self.chessboard.generate_legal_moves(GenType::All);
 
-        if moves.len() == 0 {
-            self.is_end_game = true;
+        if let Some(outcome) = self.chessboard.outcome_with_legal_moves(&moves) {
+            return modifier * self.evaluate_outcome(&outcome);
         }
 
         if depth == 0 {
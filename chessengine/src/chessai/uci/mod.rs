@@ -1,4 +1,5 @@
 use crate::chessboard::Chessboard;
+use crate::chessboard::outcome::{MaterialRule, Outcome};
 
 use super::Ai;
 
@@ -25,6 +26,7 @@ impl Ai {
                 "uci" => self.handle_uci_cmd(),
                 "ucinewgame" => (),
                 "isready" => self.handle_isready_cmd(),
+                "setoption" => self.handle_setoption_cmd(inputs),
                 "position" => self.handle_position_cmd(inputs),
                 "go" => self.handle_go_cmd(inputs),
                 "quit" => break,
@@ -34,7 +36,28 @@ impl Ai {
     }
 
     fn handle_uci_cmd(&self) {
-        println!("id name GetRusted\nid author Gilk\nuciok");
+        println!("id name GetRusted\nid author Gilk");
+        println!("option name UCI_Chess960 type check default false");
+        println!("option name MaterialRule type combo default FIDE var FIDE var USCF");
+        println!("uciok");
+    }
+
+    fn handle_setoption_cmd(&mut self, command: Vec<&str>) {
+        // setoption name <id> value <x>
+        if command.len() < 5 || command[1] != "name" || command[3] != "value" {
+            println!("Expected: setoption name <id> value <x>");
+            return;
+        }
+
+        match command[2] {
+            "UCI_Chess960" => self.chess960 = command[4] == "true",
+            "MaterialRule" => match command[4] {
+                "FIDE" => self.material_rule = MaterialRule::Fide,
+                "USCF" => self.material_rule = MaterialRule::Uscf,
+                _ => println!("Unknown material rule: {}", command[4]),
+            },
+            _ => println!("Unknown option: {}", command[2]),
+        };
     }
 
     fn handle_isready_cmd(&self) {
@@ -54,9 +77,28 @@ impl Ai {
                 2
             },
             "fen" => {
-                self.chessboard =
-                    Chessboard::new(String::from(command[2..].join(" ")));
-                8
+                let end = command.iter()
+                    .position(|&word| word == "moves")
+                    .unwrap_or(command.len());
+
+                let chessboard = match Chessboard::from_fen(&command[2..end].join(" ")) {
+                    Ok(chessboard) => chessboard,
+                    Err(err) => {
+                        println!("Invalid fen: {}", err);
+                        return;
+                    },
+                };
+
+                // Searching an impossible position gives garbage moves.
+                if let Err(errors) = chessboard.validate() {
+                    for error in errors {
+                        println!("Invalid position: {}", error);
+                    }
+                    return;
+                }
+
+                self.chessboard = chessboard;
+                end
             },
             _ => {
                 println!("Unknown position flag: {}", flag);
@@ -64,6 +106,10 @@ impl Ai {
             },
         };
 
+        // Castling is sent as king takes rook once the GUI enabled Chess960.
+        self.chessboard.chess960 |= self.chess960;
+        self.chessboard.material_rule = self.material_rule;
+
         if command.len() < index_moves + 1 {
             return;
         }
@@ -78,8 +124,13 @@ impl Ai {
 
     fn handle_moves_cmd(&mut self, moves: Vec<&str>) {
         for m in moves {
-            let mv = self.chessboard.generate_move_from_string(String::from(m));
-            self.chessboard.make_move(&mv);
+            match self.chessboard.parse_uci_move(m) {
+                Ok(mv) => self.chessboard.make_move(&mv),
+                Err(err) => {
+                    println!("Invalid move: {}", err);
+                    return;
+                },
+            }
         }
     }
 
@@ -113,10 +164,21 @@ impl Ai {
     }
 
     fn compute_best_move(&mut self) {
+        match self.chessboard.outcome() {
+            Some(outcome @ (Outcome::Checkmate(_) | Outcome::Stalemate)) => {
+                println!("info string {}", outcome);
+                println!("bestmove 0000");
+                return;
+            },
+            // Draws may still be played on until a side claims them.
+            Some(outcome) => println!("info string {}", outcome),
+            None => (),
+        }
+
         let bestmove = self.find_best_move(3);
 
         if !bestmove.is_none() {
-            println!("bestmove {}", bestmove.unwrap().to_string());
+            println!("bestmove {}", self.chessboard.move_to_uci(&bestmove.unwrap()));
         } else {
             println!("bestmove 0000");
         }
@@ -0,0 +1,121 @@
+use bitboard::attacks::{bishop_attacks, queen_attacks, rook_attacks, KING_ATTACKS, KNIGHT_ATTACKS, PAWN_ATTACKS};
+use lazy_static::lazy_static;
+
+use utils::color::Color;
+use utils::piece::Piece;
+use utils::square::Square;
+
+use super::Chessboard;
+
+lazy_static! {
+    // Squares strictly between two squares on the same line, empty otherwise.
+    pub static ref BETWEEN: [[u64; 64]; 64] = make_between();
+}
+
+fn make_between() -> [[u64; 64]; 64] {
+    let mut result = [[0u64; 64]; 64];
+
+    for (a, row) in result.iter_mut().enumerate() {
+        let from = Square::from_u32(a as u32);
+
+        for (b, squares) in row.iter_mut().enumerate() {
+            let to = Square::from_u32(b as u32);
+
+            for piece in [Piece::Bishop, Piece::Rook] {
+                if attacks_from(&piece, &Color::White, &from, 0) & to.to_bitboard() != 0 {
+                    *squares = attacks_from(&piece, &Color::White, &from, to.to_bitboard())
+                        & attacks_from(&piece, &Color::White, &to, from.to_bitboard());
+                }
+            }
+        }
+    }
+
+    result
+}
+
+pub fn between(a: &Square, b: &Square) -> u64 {
+    BETWEEN[a.to_u32() as usize][b.to_u32() as usize]
+}
+
+// Squares attacked by a piece of `color` standing on `square`, sliding
+// pieces being stopped by `occupancy`. The color only matters for pawns.
+pub fn attacks_from(piece: &Piece, color: &Color, square: &Square, occupancy: u64) -> u64 {
+    let sq = square.to_u32() as usize;
+
+    match piece {
+        Piece::Pawn => PAWN_ATTACKS[color.to_usize()][sq],
+        Piece::Knight => KNIGHT_ATTACKS[sq],
+        Piece::Bishop => bishop_attacks(sq, occupancy),
+        Piece::Rook => rook_attacks(sq, occupancy),
+        Piece::Queen => queen_attacks(sq, occupancy),
+        Piece::King => KING_ATTACKS[sq],
+    }
+}
+
+impl Chessboard {
+    // Pieces of both colors attacking `square`, sliding pieces being
+    // stopped by `occupancy` instead of the actual board.
+    pub fn attackers_to(&self, square: &Square, occupancy: u64) -> u64 {
+        let sq = square.to_u32() as usize;
+        let queens = self.get_pieces(&Piece::Queen);
+
+        // A pawn attacks the square if a pawn of the other color standing
+        // there would attack the pawn.
+        (PAWN_ATTACKS[Color::Black.to_usize()][sq] & self.get_pieces_color(&Piece::Pawn, &Color::White))
+            | (PAWN_ATTACKS[Color::White.to_usize()][sq] & self.get_pieces_color(&Piece::Pawn, &Color::Black))
+            | (KNIGHT_ATTACKS[sq] & self.get_pieces(&Piece::Knight))
+            | (bishop_attacks(sq, occupancy) & (self.get_pieces(&Piece::Bishop) | queens))
+            | (rook_attacks(sq, occupancy) & (self.get_pieces(&Piece::Rook) | queens))
+            | (KING_ATTACKS[sq] & self.get_pieces(&Piece::King))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn squares(names: &[&str]) -> u64 {
+        names.iter().fold(0, |bb, name| bb | Square::from_string(name).to_bitboard())
+    }
+
+    #[test]
+    fn test_attacks_from() {
+        let e4 = Square::from_string("e4");
+        assert_eq!(attacks_from(&Piece::Knight, &Color::White, &e4, 0).count_ones(), 8);
+        assert_eq!(attacks_from(&Piece::King, &Color::White, &Square::from_string("a1"), 0).count_ones(), 3);
+        assert_eq!(attacks_from(&Piece::Queen, &Color::White, &e4, 0).count_ones(), 27);
+        assert_eq!(attacks_from(&Piece::Pawn, &Color::White, &e4, 0), squares(&["d5", "f5"]));
+        assert_eq!(attacks_from(&Piece::Pawn, &Color::Black, &Square::from_string("a4"), 0), squares(&["b3"]));
+
+        // The rook sees up to the blocker, included.
+        let targets = attacks_from(&Piece::Rook, &Color::White, &e4, squares(&["e6"]));
+        assert_ne!(targets & squares(&["e6"]), 0);
+        assert_eq!(targets & squares(&["e7"]), 0);
+    }
+
+    #[test]
+    fn test_between() {
+        let a1 = Square::from_string("a1");
+        assert_eq!(between(&a1, &Square::from_string("a4")), squares(&["a2", "a3"]));
+        assert_eq!(between(&a1, &Square::from_string("d4")), squares(&["b2", "c3"]));
+        assert_eq!(between(&Square::from_string("h1"), &Square::from_string("e1")), squares(&["f1", "g1"]));
+        assert_eq!(between(&a1, &Square::from_string("b2")), 0);
+        assert_eq!(between(&a1, &Square::from_string("b3")), 0);
+    }
+
+    #[test]
+    fn test_attackers_to() {
+        let chessboard = Chessboard::new("4k3/8/2n5/3p4/1B2R3/8/8/4K3 w - - 0 1".to_string());
+        let occupancy = !chessboard.empty_board;
+
+        assert_eq!(chessboard.attackers_to(&Square::from_string("e4"), occupancy), squares(&["d5"]));
+        assert_eq!(chessboard.attackers_to(&Square::from_string("d4"), occupancy), squares(&["c6", "e4"]));
+        assert_eq!(chessboard.attackers_to(&Square::from_string("e2"), occupancy), squares(&["e1", "e4"]));
+        assert_eq!(chessboard.attackers_to(&Square::from_string("e7"), occupancy), squares(&["e8", "b4", "c6", "e4"]));
+
+        // Removing the bishop from the occupancy lets the rook see through.
+        let a4 = Square::from_string("a4");
+        assert_eq!(chessboard.attackers_to(&a4, occupancy), 0);
+        assert_eq!(chessboard.attackers_to(&a4, occupancy ^ squares(&["b4"])), squares(&["e4"]));
+    }
+}
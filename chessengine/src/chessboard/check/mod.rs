@@ -0,0 +1,180 @@
+use bitboard::attacks::{bishop_attacks, rook_attacks, KNIGHT_ATTACKS, PAWN_ATTACKS};
+
+use utils::piece::Piece;
+use utils::square::Square;
+
+use super::Chessboard;
+use super::attacks::{attacks_from, between};
+use super::moves::Move;
+use super::moves::piece::king;
+
+// The squares from which each piece of the side to move would attack the
+// enemy king, and the pieces standing alone between the enemy king and a
+// slider of the side to move. Computed once per position and shared by the
+// moves tested in it.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct CheckInfo {
+    pub check_squares: [u64; 6],
+    pub discoverers: u64,
+}
+
+impl Chessboard {
+    pub fn compute_check_info(&self) -> CheckInfo {
+        let them = self.turn.opposite();
+        let king = self.get_pieces_color(&Piece::King, &them);
+        if king == 0 {
+            return CheckInfo::default();
+        }
+
+        let sq = king.trailing_zeros() as usize;
+        let king_square = Square::from_u32(sq as u32);
+        let occupancy = !self.empty_board;
+        let bishop = bishop_attacks(sq, occupancy);
+        let rook = rook_attacks(sq, occupancy);
+
+        let queens = self.get_pieces_color(&Piece::Queen, &self.turn);
+        let mut snipers = (bishop_attacks(sq, 0) & (self.get_pieces_color(&Piece::Bishop, &self.turn) | queens))
+            | (rook_attacks(sq, 0) & (self.get_pieces_color(&Piece::Rook, &self.turn) | queens));
+        let mut discoverers = 0;
+
+        while snipers != 0 {
+            let blockers = between(&king_square, &Square::from_u32(snipers.trailing_zeros())) & occupancy;
+
+            if blockers.count_ones() == 1 {
+                discoverers |= blockers & self.get_colors(&self.turn);
+            }
+
+            snipers &= snipers - 1;
+        }
+
+        CheckInfo {
+            // A pawn of the side to move checks from where an enemy pawn on
+            // the king square would attack.
+            check_squares: [PAWN_ATTACKS[them.to_usize()][sq], KNIGHT_ATTACKS[sq], bishop, rook, bishop | rook, 0],
+            discoverers,
+        }
+    }
+
+    // Whether a legal move checks the enemy king, without playing it.
+    // `check_info` is the one of this position.
+    pub fn gives_check(&self, mv: &Move, check_info: &CheckInfo) -> bool {
+        let king = self.get_pieces_color(&Piece::King, &self.turn.opposite());
+        if king == 0 {
+            return false;
+        }
+
+        let king_square = Square::from_u32(king.trailing_zeros());
+        let from = mv.from();
+        let to = mv.to();
+        let piece = self.get_piece(&from).unwrap();
+
+        if mv.is_castling() {
+            let (king_dest, rook_dest) = king::castling_destinations(from.rank, mv.is_queen_castle());
+            let occupancy = (!self.empty_board ^ from.to_bitboard() ^ to.to_bitboard())
+                | king_dest.to_bitboard() | rook_dest.to_bitboard();
+            let rooks = (self.get_pieces_color(&Piece::Rook, &self.turn) ^ to.to_bitboard()) | rook_dest.to_bitboard();
+
+            return self.sliders_see(&king_square, occupancy, rooks);
+        }
+
+        if check_info.check_squares[piece.to_usize()] & to.to_bitboard() != 0 {
+            return true;
+        }
+
+        // The piece leaves the line between a slider and the king.
+        if check_info.discoverers & from.to_bitboard() != 0
+            && between(&from, &king_square) & to.to_bitboard() == 0
+            && between(&to, &king_square) & from.to_bitboard() == 0
+        {
+            return true;
+        }
+
+        let occupancy = !self.empty_board ^ from.to_bitboard();
+
+        if let Some(promotion) = mv.promotion() {
+            return attacks_from(&promotion, &self.turn, &to, occupancy) & king != 0;
+        }
+
+        // Removing the taken pawn may open a line as well.
+        if mv.is_en_passant() {
+            let occupancy = (occupancy ^ Square::new(to.file, from.rank).to_bitboard()) | to.to_bitboard();
+
+            return self.sliders_see(&king_square, occupancy, self.get_pieces_color(&Piece::Rook, &self.turn));
+        }
+
+        false
+    }
+
+    // Whether a slider of the side to move sees the square through
+    // `occupancy`, its rooks standing on `rooks`.
+    fn sliders_see(&self, square: &Square, occupancy: u64, rooks: u64) -> bool {
+        let sq = square.to_u32() as usize;
+        let queens = self.get_pieces_color(&Piece::Queen, &self.turn);
+
+        (bishop_attacks(sq, occupancy) & (self.get_pieces_color(&Piece::Bishop, &self.turn) | queens))
+            | (rook_attacks(sq, occupancy) & (rooks | queens))
+            != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessboard::random_games::play_random_games;
+
+    fn gives_check(fen: &str, uci: &str) -> bool {
+        let chessboard = Chessboard::new(fen.to_string());
+        chessboard.gives_check(&chessboard.parse_uci_move(uci).unwrap(), &chessboard.compute_check_info())
+    }
+
+    #[test]
+    fn test_gives_check() {
+        // Direct checks.
+        assert!(gives_check("4k3/8/8/8/4N3/8/8/4K3 w - - 0 1", "e4f6"));
+        assert!(gives_check("4k3/8/5P2/8/8/8/8/4K3 w - - 0 1", "f6f7"));
+        assert!(gives_check("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"));
+        assert!(!gives_check("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a7"));
+        assert!(!gives_check("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1", "d2d4"));
+
+        // Discovered checks, unless the piece stays on the line.
+        assert!(gives_check("4k3/8/8/4B3/8/8/8/4R1K1 w - - 0 1", "e5c3"));
+        assert!(!gives_check("4k3/8/8/4P3/8/8/8/4R1K1 w - - 0 1", "e5e6"));
+
+        // En passant, checking directly or opening a line, possibly through
+        // the square of the pawn taken.
+        assert!(gives_check("8/2k5/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"));
+        assert!(gives_check("7k/8/8/3pP3/8/8/8/B3K3 w - d6 0 1", "e5d6"));
+        assert!(gives_check("8/8/8/R2pP2k/8/8/8/4K3 w - d6 0 1", "e5d6"));
+        assert!(!gives_check("8/8/8/K2pP2k/8/8/8/8 w - d6 0 1", "e5d6"));
+
+        // Promotions, the new piece seeing through the square the pawn left.
+        assert!(gives_check("k7/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8q"));
+        assert!(gives_check("3r4/4P3/5k2/8/8/8/8/4K3 w - - 0 1", "e7d8q"));
+        assert!(!gives_check("3r4/4P3/5k2/8/8/8/8/4K3 w - - 0 1", "e7d8r"));
+        assert!(gives_check("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1", "e7e8n"));
+        assert!(!gives_check("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1", "e7e8q"));
+
+        // Castling, the rook giving check.
+        assert!(gives_check("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"));
+        assert!(gives_check("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", "e1c1"));
+        assert!(!gives_check("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1g1"));
+    }
+
+    // Random games, checking each legal move against playing it.
+    #[test]
+    fn test_gives_check_random_games() {
+        play_random_games(3, 80, 0x2545f4914f6cdd1d, |chessboard, moves, _| {
+            let check_info = chessboard.compute_check_info();
+
+            for mv in moves.iter() {
+                let expected = {
+                    chessboard.make_move(mv);
+                    let check = chessboard.is_in_check(&chessboard.turn);
+                    chessboard.unmake_move(mv);
+                    check
+                };
+                assert_eq!(chessboard.gives_check(mv, &check_info), expected, "{:?} in {}", mv, chessboard.to_fen());
+            }
+        });
+    }
+}
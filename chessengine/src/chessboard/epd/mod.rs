@@ -0,0 +1,326 @@
+use std::fmt;
+
+use super::Chessboard;
+use super::fen::FenError;
+use super::moves::Move;
+use super::san::SanError;
+
+// A position with its operations, one line of an EPD file. The move counters,
+// hmvc and fmvn, are read into the position. Operations this module does not
+// know are kept as they were read.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Epd {
+    pub chessboard: Chessboard,
+    // bm and am.
+    pub best_moves: Vec<Move>,
+    pub avoid_moves: Vec<Move>,
+    pub id: Option<String>,
+    // c0 to c9.
+    pub comments: [Option<String>; 10],
+    // D1 to Dn: perft depth and node count.
+    pub perft: Vec<(u8, u64)>,
+    // ce, in centipawns for the side to move.
+    pub centipawns: Option<i32>,
+    // dm, in moves.
+    pub mate: Option<i32>,
+    pub other: Vec<(String, Vec<String>)>,
+}
+
+// Offsets are byte positions in the line.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum EpdError {
+    Position(FenError),
+    Move { opcode: String, error: SanError },
+    UnterminatedString { offset: usize },
+    MissingOperand { opcode: String },
+    InvalidOperand { opcode: String, operand: String },
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EpdError::Position(error) => write!(f, "invalid position: {}", error),
+            EpdError::Move { opcode, error } => write!(f, "{} in {}", error, opcode),
+            EpdError::UnterminatedString { offset } =>
+                write!(f, "unterminated string at offset {}", offset),
+            EpdError::MissingOperand { opcode } => write!(f, "missing operand for {}", opcode),
+            EpdError::InvalidOperand { opcode, operand } =>
+                write!(f, "invalid operand {} for {}", operand, opcode),
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+// Offset in `line` of the end of the fourth field.
+fn position_end(line: &str) -> usize {
+    let mut fields = 0;
+    let mut in_field = false;
+
+    for (offset, c) in line.char_indices() {
+        match (c.is_whitespace(), in_field) {
+            (true, true) => {
+                fields += 1;
+                in_field = false;
+
+                if fields == 4 {
+                    return offset;
+                }
+            },
+            (false, false) => in_field = true,
+            _ => (),
+        }
+    }
+
+    line.len()
+}
+
+// Operations as lists of words, the opcode first. Quoted strings make one
+// word and may hold spaces and semicolons.
+fn split_operations(line: &str, start: usize) -> Result<Vec<Vec<String>>, EpdError> {
+    let mut operations = Vec::new();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut chars = line[start..].char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => {
+                let text: String = chars.by_ref().map(|(_, c)| c).take_while(|c| *c != '"').collect();
+                if !line[start + index + 1..].contains('"') {
+                    return Err(EpdError::UnterminatedString { offset: start + index });
+                }
+                words.push(text);
+            },
+            ';' | ' ' | '\t' => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+
+                if c == ';' && !words.is_empty() {
+                    operations.push(std::mem::take(&mut words));
+                }
+            },
+            _ => word.push(c),
+        }
+    }
+
+    // The last semicolon is often left out.
+    if !word.is_empty() {
+        words.push(word);
+    }
+    if !words.is_empty() {
+        operations.push(words);
+    }
+
+    Ok(operations)
+}
+
+fn parse_number<T: std::str::FromStr>(opcode: &str, operand: &str) -> Result<T, EpdError> {
+    operand.parse().map_err(|_| EpdError::InvalidOperand {
+        opcode: opcode.to_string(),
+        operand: operand.to_string(),
+    })
+}
+
+// Words holding spaces, semicolons or nothing are written quoted.
+fn quote(word: &str) -> String {
+    match word.is_empty() || word.contains([' ', '\t', ';']) {
+        true => format!("\"{}\"", word),
+        false => word.to_string(),
+    }
+}
+
+impl Epd {
+    pub fn new(chessboard: Chessboard) -> Epd {
+        Epd {
+            chessboard,
+            best_moves: Vec::new(),
+            avoid_moves: Vec::new(),
+            id: None,
+            comments: Default::default(),
+            perft: Vec::new(),
+            centipawns: None,
+            mate: None,
+            other: Vec::new(),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Epd, EpdError> {
+        let end = position_end(line);
+        let chessboard = Chessboard::from_fen(&line[..end]).map_err(EpdError::Position)?;
+        let mut epd = Epd::new(chessboard);
+
+        for words in split_operations(line, end)? {
+            let opcode = words[0].as_str();
+            let operands = &words[1..];
+            let first = operands.first().ok_or_else(|| EpdError::MissingOperand { opcode: opcode.to_string() });
+
+            match opcode {
+                "bm" | "am" => {
+                    first?;
+                    let moves = operands.iter()
+                        .map(|san| epd.chessboard.parse_san(san))
+                        .collect::<Result<Vec<Move>, SanError>>()
+                        .map_err(|error| EpdError::Move { opcode: opcode.to_string(), error })?;
+
+                    match opcode {
+                        "bm" => epd.best_moves = moves,
+                        _ => epd.avoid_moves = moves,
+                    }
+                },
+                "id" => {
+                    first?;
+                    epd.id = Some(operands.join(" "));
+                },
+                "hmvc" => epd.chessboard.halfmove_clock = parse_number(opcode, first?)?,
+                "fmvn" => epd.chessboard.fullmove_number = parse_number(opcode, first?)?,
+                "ce" => epd.centipawns = Some(parse_number(opcode, first?)?),
+                "dm" => epd.mate = Some(parse_number(opcode, first?)?),
+                _ => {
+                    let comment = opcode.strip_prefix('c')
+                        .filter(|digit| digit.len() == 1)
+                        .and_then(|digit| digit.parse::<usize>().ok());
+                    let depth = opcode.strip_prefix('D')
+                        .and_then(|depth| depth.parse::<u8>().ok())
+                        .filter(|depth| *depth > 0);
+
+                    match (comment, depth) {
+                        (Some(index), _) => {
+                            first?;
+                            epd.comments[index] = Some(operands.join(" "));
+                        },
+                        (_, Some(depth)) => epd.perft.push((depth, parse_number(opcode, first?)?)),
+                        _ => epd.other.push((opcode.to_string(), operands.to_vec())),
+                    }
+                },
+            }
+        }
+
+        Ok(epd)
+    }
+
+    pub fn to_epd(&self) -> String {
+        let fen = self.chessboard.to_fen();
+        let mut epd = fen.split(' ').take(4).collect::<Vec<&str>>().join(" ");
+        let mut chessboard = self.chessboard.clone();
+
+        // The move counters are written unless they are the ones a position
+        // without them reads as.
+        if chessboard.halfmove_clock != 0 {
+            epd.push_str(&format!(" hmvc {};", chessboard.halfmove_clock));
+        }
+        if chessboard.fullmove_number != 1 {
+            epd.push_str(&format!(" fmvn {};", chessboard.fullmove_number));
+        }
+        for (opcode, moves) in [("bm", &self.best_moves), ("am", &self.avoid_moves)] {
+            if !moves.is_empty() {
+                let sans = chessboard.moves_to_san(moves);
+                epd.push_str(&format!(" {} {};", opcode, sans.join(" ")));
+            }
+        }
+
+        if let Some(centipawns) = self.centipawns {
+            epd.push_str(&format!(" ce {};", centipawns));
+        }
+        if let Some(mate) = self.mate {
+            epd.push_str(&format!(" dm {};", mate));
+        }
+        if let Some(id) = &self.id {
+            epd.push_str(&format!(" id \"{}\";", id));
+        }
+        for (index, comment) in self.comments.iter().enumerate() {
+            if let Some(comment) = comment {
+                epd.push_str(&format!(" c{} \"{}\";", index, comment));
+            }
+        }
+        for (depth, nodes) in &self.perft {
+            epd.push_str(&format!(" D{} {};", depth, nodes));
+        }
+        for (opcode, operands) in &self.other {
+            let words: Vec<String> = std::iter::once(opcode.clone())
+                .chain(operands.iter().map(|operand| quote(operand)))
+                .collect();
+            epd.push_str(&format!(" {};", words.join(" ")));
+        }
+
+        epd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_epd() {
+        let line = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - bm Qxf6 dxe6; am O-O; \
+            id \"kiwipete; perft\"; c0 \"Peter McKenzie\"; c9 x; D1 48; D2 2039; ce -35; dm 4; hmvc 0; pv Qxf6 Bxf6";
+        let epd = Epd::parse(line).unwrap();
+
+        assert_eq!(epd.chessboard.to_fen(), "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let uci: Vec<String> = epd.best_moves.iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(uci, vec!["f3f6", "d5e6"]);
+        assert!(epd.avoid_moves[0].is_castling() && !epd.avoid_moves[0].is_queen_castle());
+        assert_eq!(epd.id, Some(String::from("kiwipete; perft")));
+        assert_eq!(epd.comments[0], Some(String::from("Peter McKenzie")));
+        assert_eq!(epd.comments[9], Some(String::from("x")));
+        assert_eq!(epd.comments[1..9], [None, None, None, None, None, None, None, None]);
+        assert_eq!((epd.chessboard.halfmove_clock, epd.chessboard.fullmove_number), (0, 1));
+        assert_eq!(epd.perft, vec![(1, 48), (2, 2039)]);
+        assert_eq!(epd.centipawns, Some(-35));
+        assert_eq!(epd.mate, Some(4));
+        assert_eq!(epd.other, vec![(String::from("pv"), vec![String::from("Qxf6"), String::from("Bxf6")])]);
+
+        // The written record reads back the same.
+        assert_eq!(Epd::parse(&epd.to_epd()), Ok(epd));
+
+        // The move counters are read into the position.
+        let epd = Epd::parse("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - fmvn 31; hmvc 7; D1 14").unwrap();
+        assert_eq!(epd.chessboard.to_fen(), "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 7 31");
+        assert!(epd.other.is_empty());
+
+        let epd = Epd::parse("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf -").unwrap();
+        assert!(epd.chessboard.chess960);
+        assert_eq!(epd.to_epd(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq -");
+        assert_eq!(Epd::parse(&epd.to_epd()), Ok(epd));
+    }
+
+    #[test]
+    fn test_write_epd() {
+        let line = "6k1/5ppp/8/8/8/8/8/R3K3 w Q - bm Ra8#; ce 32767; id \"mate in one\"; D1 16; pv Ra8 \"two words\";";
+        assert_eq!(Epd::parse(line).unwrap().to_epd(), line);
+
+        let mut epd = Epd::new(Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string()));
+        epd.best_moves.push(epd.chessboard.parse_uci_move("e2e4").unwrap());
+        epd.perft = vec![(1, 20), (2, 400)];
+        epd.comments[3] = Some(String::from("start"));
+        assert_eq!(epd.to_epd(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4; c3 \"start\"; D1 20; D2 400;");
+
+        // A position with its move counters reads back the same.
+        let mut epd = Epd::new(Chessboard::new("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3".to_string()));
+        epd.best_moves.push(epd.chessboard.parse_uci_move("f1b5").unwrap());
+        assert_eq!(epd.to_epd(), "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - hmvc 2; fmvn 3; bm Bb5;");
+        assert_eq!(Epd::parse(&epd.to_epd()), Ok(epd));
+    }
+
+    #[test]
+    fn test_parse_epd_errors() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+        let parse = |operations: &str| Epd::parse(&format!("{} {}", start, operations));
+
+        assert!(matches!(Epd::parse("rnbqkbnr/pppppppp/8/8 w KQkq -"), Err(EpdError::Position(_))));
+        assert!(matches!(parse("bm Ke2;"), Err(EpdError::Move { error: SanError::IllegalMove(_), .. })));
+        assert_eq!(parse("id \"open"), Err(EpdError::UnterminatedString { offset: start.len() + 4 }));
+        assert_eq!(parse("bm;"), Err(EpdError::MissingOperand { opcode: String::from("bm") }));
+        assert_eq!(parse("D2;"), Err(EpdError::MissingOperand { opcode: String::from("D2") }));
+        assert_eq!(parse("D1 many;"), Err(EpdError::InvalidOperand { opcode: String::from("D1"), operand: String::from("many") }));
+        assert_eq!(parse("hmvc -1;"), Err(EpdError::InvalidOperand { opcode: String::from("hmvc"), operand: String::from("-1") }));
+        assert_eq!(parse("ce +1.5;"), Err(EpdError::InvalidOperand { opcode: String::from("ce"), operand: String::from("+1.5") }));
+
+        // Opcodes only looking like the known ones are kept as they are.
+        let epd = parse("D0 1; c10 x; Dx 2;").unwrap();
+        assert!(epd.perft.is_empty() && epd.comments.iter().all(|comment| comment.is_none()));
+        assert_eq!(epd.other.len(), 3);
+    }
+}
@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use utils::color::Color;
+use utils::file::File;
+use utils::piece::Piece;
+use utils::rank::{Rank, RANKS};
+use utils::square::Square;
+
+use super::Chessboard;
+use super::outcome::MaterialRule;
+use super::moves::piece::king;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FenField {
+    PiecePlacement,
+    SideToMove,
+    CastlingRights,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+// Offsets are byte positions in the input string.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum FenError {
+    MissingField { field: FenField, offset: usize },
+    TooManyFields { offset: usize },
+    InvalidPiece { offset: usize, found: char },
+    InvalidRank { offset: usize },
+    InvalidSideToMove { offset: usize },
+    InvalidCastling { offset: usize, found: char },
+    InvalidEnPassant { offset: usize },
+    InvalidNumber { field: FenField, offset: usize },
+}
+
+impl FenField {
+    fn name(&self) -> &'static str {
+        match self {
+            FenField::PiecePlacement => "piece placement",
+            FenField::SideToMove => "side to move",
+            FenField::CastlingRights => "castling rights",
+            FenField::EnPassant => "en passant square",
+            FenField::HalfmoveClock => "halfmove clock",
+            FenField::FullmoveNumber => "fullmove number",
+        }
+    }
+}
+
+impl FenError {
+    pub fn field(&self) -> Option<FenField> {
+        match self {
+            FenError::MissingField { field, .. } => Some(*field),
+            FenError::TooManyFields { .. } => None,
+            FenError::InvalidPiece { .. } => Some(FenField::PiecePlacement),
+            FenError::InvalidRank { .. } => Some(FenField::PiecePlacement),
+            FenError::InvalidSideToMove { .. } => Some(FenField::SideToMove),
+            FenError::InvalidCastling { .. } => Some(FenField::CastlingRights),
+            FenError::InvalidEnPassant { .. } => Some(FenField::EnPassant),
+            FenError::InvalidNumber { field, .. } => Some(*field),
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        match self {
+            FenError::MissingField { offset, .. }
+            | FenError::TooManyFields { offset }
+            | FenError::InvalidPiece { offset, .. }
+            | FenError::InvalidRank { offset }
+            | FenError::InvalidSideToMove { offset }
+            | FenError::InvalidCastling { offset, .. }
+            | FenError::InvalidEnPassant { offset }
+            | FenError::InvalidNumber { offset, .. } => *offset,
+        }
+    }
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::MissingField { field, offset } =>
+                write!(f, "missing {} at offset {}", field.name(), offset),
+            FenError::TooManyFields { offset } =>
+                write!(f, "unexpected field at offset {}", offset),
+            FenError::InvalidPiece { offset, found } =>
+                write!(f, "invalid piece '{}' at offset {}", found, offset),
+            FenError::InvalidRank { offset } =>
+                write!(f, "rank does not describe 8 squares at offset {}", offset),
+            FenError::InvalidSideToMove { offset } =>
+                write!(f, "invalid side to move at offset {}", offset),
+            FenError::InvalidCastling { offset, found } =>
+                write!(f, "invalid castling right '{}' at offset {}", found, offset),
+            FenError::InvalidEnPassant { offset } =>
+                write!(f, "invalid en passant square at offset {}", offset),
+            FenError::InvalidNumber { field, offset } =>
+                write!(f, "invalid {} at offset {}", field.name(), offset),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+// Split on whitespace, keeping the offset of each field.
+fn split_fields(fen: &str) -> Vec<(usize, &str)> {
+    let mut fields = Vec::new();
+    let mut start = None;
+
+    for (offset, c) in fen.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(begin)) => {
+                fields.push((begin, &fen[begin..offset]));
+                start = None;
+            },
+            (false, None) => start = Some(offset),
+            _ => (),
+        }
+    }
+
+    if let Some(begin) = start {
+        fields.push((begin, &fen[begin..]));
+    }
+
+    fields
+}
+
+fn parse_piece(c: char) -> Option<(Piece, Color)> {
+    let piece = match c.to_ascii_lowercase() {
+        'p' => Piece::Pawn,
+        'n' => Piece::Knight,
+        'b' => Piece::Bishop,
+        'r' => Piece::Rook,
+        'q' => Piece::Queen,
+        'k' => Piece::King,
+        _ => return None,
+    };
+
+    let color = if c.is_ascii_lowercase() {
+        Color::Black
+    } else {
+        Color::White
+    };
+
+    Some((piece, color))
+}
+
+fn parse_square(field: &str) -> Option<Square> {
+    let mut chars = field.chars();
+    let file = chars.next()?;
+    let rank = chars.next()?;
+
+    if chars.next().is_some() || !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+        return None;
+    }
+
+    Some(Square::new(File::from_char(file), Rank::from_char(rank)))
+}
+
+// File of the king on its back rank, e when it is not there.
+fn king_file(piece_board: &[u64], color_board: &[u64], color: &Color) -> File {
+    let king = piece_board[Piece::King.to_usize()]
+        & color_board[color.to_usize()]
+        & RANKS[king::back_rank(color) as usize];
+
+    match king {
+        0 => File::E,
+        _ => File::from_u32(king.trailing_zeros() % 8),
+    }
+}
+
+// Rook furthest from the king on the given side of the back rank, which is
+// what K and Q refer to in X-FEN.
+fn outermost_rook(rooks: u64, color: &Color, king_file: File, queen_side: bool) -> Option<File> {
+    let rooks = (rooks >> (king::back_rank(color) as u32 * 8)) & 0xff;
+    let side = match queen_side {
+        true => (1 << king_file as u32) - 1,
+        false => 0xff << (king_file as u32 + 1) & 0xff,
+    };
+
+    match (rooks & side, queen_side) {
+        (0, _) => None,
+        (rooks, true) => Some(File::from_u32(rooks.trailing_zeros())),
+        (rooks, false) => Some(File::from_u32(63 - rooks.leading_zeros())),
+    }
+}
+
+impl Chessboard {
+    pub fn from_fen(fen: &str) -> Result<Chessboard, FenError> {
+        let fields = split_fields(fen);
+        let field = |index: usize, name: FenField| {
+            fields.get(index).copied().ok_or(FenError::MissingField {
+                field: name,
+                offset: fen.len(),
+            })
+        };
+
+        if let Some((offset, _)) = fields.get(6) {
+            return Err(FenError::TooManyFields { offset: *offset });
+        }
+
+        // Parse the piece board.
+        let (start, piece_placement) = field(0, FenField::PiecePlacement)?;
+        let mut piece_board = vec![0; 7];
+        let mut color_board = vec![0; 2];
+        let mut mailbox = [None; 64];
+        let mut empty_board = 0xff_ff_ff_ff_ff_ff_ff_ff;
+        let mut rank = 7;
+        let mut file = 0;
+
+        for (index, c) in piece_placement.char_indices() {
+            let offset = start + index;
+
+            if c == '/' {
+                if file != 8 || rank == 0 {
+                    return Err(FenError::InvalidRank { offset });
+                }
+                rank -= 1;
+                file = 0;
+            } else if let Some(skip_count) = c.to_digit(10) {
+                if skip_count == 0 || file + skip_count > 8 {
+                    return Err(FenError::InvalidRank { offset });
+                }
+                file += skip_count;
+            } else {
+                let (piece, color) = match parse_piece(c) {
+                    Some(piece) => piece,
+                    None => return Err(FenError::InvalidPiece { offset, found: c }),
+                };
+
+                if file == 8 {
+                    return Err(FenError::InvalidRank { offset });
+                }
+
+                let square: u64 = 1 << (rank * 8 + file);
+                piece_board[piece.to_usize()] |= square;
+                color_board[color.to_usize()] |= square;
+                empty_board &= !square;
+                mailbox[(rank * 8 + file) as usize] = Some((piece, color));
+                file += 1;
+            }
+        }
+
+        if file != 8 || rank != 0 {
+            return Err(FenError::InvalidRank { offset: start + piece_placement.len() });
+        }
+
+        // Parse the turn board.
+        let (offset, side) = field(1, FenField::SideToMove)?;
+        let turn = match side {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::InvalidSideToMove { offset }),
+        };
+
+        // Parse castling rights, as X-FEN (KQkq) or Shredder-FEN (rook files).
+        let (start, castling) = field(2, FenField::CastlingRights)?;
+        let mut white_castle = (None, None);
+        let mut black_castle = (None, None);
+
+        if castling != "-" {
+            for (index, c) in castling.char_indices() {
+                let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+                let rooks = piece_board[Piece::Rook.to_usize()] & color_board[color.to_usize()];
+                let king_file = king_file(&piece_board, &color_board, &color);
+
+                let (queen_side, rook_file) = match c.to_ascii_lowercase() {
+                    'k' => (false, outermost_rook(rooks, &color, king_file, false).unwrap_or(File::H)),
+                    'q' => (true, outermost_rook(rooks, &color, king_file, true).unwrap_or(File::A)),
+                    'a'..='h' => {
+                        let file = File::from_char(c.to_ascii_lowercase());
+                        (file < king_file, file)
+                    },
+                    _ => return Err(FenError::InvalidCastling { offset: start + index, found: c }),
+                };
+
+                let rights = match color {
+                    Color::White => &mut white_castle,
+                    Color::Black => &mut black_castle,
+                };
+                match queen_side {
+                    true => rights.1 = Some(rook_file),
+                    false => rights.0 = Some(rook_file),
+                }
+            }
+        }
+
+        // Anything else than a king on e and rooks on a/h needs Chess960
+        // castling.
+        let chess960 = [(Color::White, white_castle), (Color::Black, black_castle)]
+            .iter()
+            .any(|(color, (king_side, queen_side))| {
+                (king_side.is_some() || queen_side.is_some())
+                    && king_file(&piece_board, &color_board, color) != File::E
+                    || king_side.is_some_and(|file| file != File::H)
+                    || queen_side.is_some_and(|file| file != File::A)
+            });
+
+        // Parse the en passant square.
+        let (offset, ep) = field(3, FenField::EnPassant)?;
+        let en_passant = if ep == "-" {
+            None
+        } else {
+            match parse_square(ep) {
+                Some(square) => Some(square),
+                None => return Err(FenError::InvalidEnPassant { offset }),
+            }
+        };
+
+        // The move counters are often left out, default them.
+        let halfmove_clock = match fields.get(4) {
+            Some((offset, clock)) => clock.parse().map_err(|_| FenError::InvalidNumber {
+                field: FenField::HalfmoveClock,
+                offset: *offset,
+            })?,
+            None => 0,
+        };
+
+        let fullmove_number = match fields.get(5) {
+            Some((offset, number)) => number.parse().map_err(|_| FenError::InvalidNumber {
+                field: FenField::FullmoveNumber,
+                offset: *offset,
+            })?,
+            None => 1,
+        };
+
+        let mut chessboard = Chessboard {
+            piece_board,
+            color_board,
+            mailbox,
+            empty_board,
+            turn,
+            white_castle,
+            black_castle,
+            chess960,
+            material_rule: MaterialRule::Fide,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+            perft_depth: 0,
+            history: Vec::new(),
+            mv_hashmap: HashMap::new(),
+            hash: 0,
+            checkers: 0,
+        };
+        chessboard.hash = chessboard.compute_hash();
+        chessboard.checkers = chessboard.compute_checkers();
+
+        Ok(chessboard)
+    }
+}
+
+impl Chessboard {
+    // Standard six-field FEN. The en passant square is written as stored,
+    // which makes `from_fen(to_fen())` an exact round trip.
+    pub fn to_fen(&self) -> String {
+        self.format_fen(false)
+    }
+
+    // Same as `to_fen`, but the en passant square is only written when a
+    // pawn can actually capture on it.
+    pub fn to_fen_capturable_ep(&self) -> String {
+        self.format_fen(true)
+    }
+
+    fn format_fen(&self, capturable_ep: bool) -> String {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty_count = 0;
+
+            for file in 0..8 {
+                let square = Square::from_u32(rank * 8 + file);
+
+                match (self.get_piece(&square), self.get_color(&square)) {
+                    (Some(piece), Some(color)) => {
+                        if empty_count > 0 {
+                            fen.push_str(&empty_count.to_string());
+                            empty_count = 0;
+                        }
+
+                        let c = piece.to_char();
+                        fen.push(match color {
+                            Color::White => c.to_ascii_uppercase(),
+                            Color::Black => c,
+                        });
+                    },
+                    _ => empty_count += 1,
+                }
+            }
+
+            if empty_count > 0 {
+                fen.push_str(&empty_count.to_string());
+            }
+
+            if rank != 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push(' ');
+        fen.push(self.turn.to_char());
+        fen.push(' ');
+
+        let mut castle_string = String::new();
+
+        for color in [Color::White, Color::Black] {
+            let rooks = self.get_pieces_color(&Piece::Rook, &color);
+            let king_file = king_file(&self.piece_board, &self.color_board, &color);
+            let (king_side, queen_side) = self.castling_rights(&color);
+
+            for (rook_file, queen_side) in [(king_side, false), (queen_side, true)] {
+                let rook_file = match rook_file {
+                    Some(file) => file,
+                    None => continue,
+                };
+
+                // Shredder-FEN letter when K or Q would pick another rook.
+                let c = if outermost_rook(rooks, &color, king_file, queen_side) != Some(rook_file) {
+                    rook_file.to_char()
+                } else if queen_side {
+                    'q'
+                } else {
+                    'k'
+                };
+
+                castle_string.push(match color {
+                    Color::White => c.to_ascii_uppercase(),
+                    Color::Black => c,
+                });
+            }
+        }
+
+        if castle_string.is_empty() {
+            castle_string.push('-');
+        }
+
+        fen.push_str(&castle_string);
+        fen.push(' ');
+
+        match self.en_passant {
+            Some(square) if !capturable_ep || self.is_en_passant_capturable() => {
+                fen.push_str(&square.to_string());
+            },
+            _ => fen.push('-'),
+        }
+
+        format!("{} {} {}", fen, self.halfmove_clock, self.fullmove_number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_fen_error(fen: &str, expected: FenError) {
+        assert_eq!(Chessboard::from_fen(fen), Err(expected));
+    }
+
+    #[test]
+    fn test_from_fen() {
+        let chessboard = Chessboard::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b Kq e3 3 12").unwrap();
+        assert_eq!(chessboard.get_piece(&Square::from_string("e4")), Some(Piece::Pawn));
+        assert_eq!(chessboard.get_color(&Square::from_string("e4")), Some(Color::White));
+        assert_eq!(chessboard.get_color(&Square::from_string("d8")), Some(Color::Black));
+        assert_eq!(chessboard.turn, Color::Black);
+        assert_eq!(chessboard.white_castle, (Some(File::H), None));
+        assert_eq!(chessboard.black_castle, (None, Some(File::A)));
+        assert!(!chessboard.chess960);
+        assert_eq!(chessboard.en_passant, Some(Square::from_string("e3")));
+        assert_eq!(chessboard.halfmove_clock, 3);
+        assert_eq!(chessboard.fullmove_number, 12);
+    }
+
+    #[test]
+    fn test_from_fen_without_counters() {
+        let chessboard = Chessboard::from_fen("4k3/8/8/8/8/8/8/4K3 w - -").unwrap();
+        assert_eq!(chessboard.halfmove_clock, 0);
+        assert_eq!(chessboard.fullmove_number, 1);
+
+        let chessboard = Chessboard::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 12").unwrap();
+        assert_eq!(chessboard.halfmove_clock, 12);
+        assert_eq!(chessboard.fullmove_number, 1);
+    }
+
+    #[test]
+    fn test_from_fen_errors() {
+        test_fen_error("", FenError::MissingField { field: FenField::PiecePlacement, offset: 0 });
+        test_fen_error("4k3/8/8/8/8/8/8/4K3", FenError::MissingField { field: FenField::SideToMove, offset: 19 });
+        test_fen_error("4k3/8/8/8/8/8/8/4K3 w -", FenError::MissingField { field: FenField::EnPassant, offset: 23 });
+        test_fen_error("4k3/8/8/8/8/8/8/4K3 w - - 0 1 2", FenError::TooManyFields { offset: 30 });
+
+        test_fen_error("4k3/8/8/8/8/8/8/4F3 w - - 0 1", FenError::InvalidPiece { offset: 17, found: 'F' });
+        test_fen_error("4k3/8/8/8/8/8/8/4K4 w - - 0 1", FenError::InvalidRank { offset: 18 });
+        test_fen_error("4k3/8/8/8/8/8/8/4K2 w - - 0 1", FenError::InvalidRank { offset: 19 });
+        test_fen_error("4k3/8/8/8/8/8/4K3 w - - 0 1", FenError::InvalidRank { offset: 17 });
+        test_fen_error("4k3/8/8/8/8/8/8/8/4K3 w - - 0 1", FenError::InvalidRank { offset: 17 });
+        test_fen_error("4k3/8/8/8/8/8/8/4K3 x - - 0 1", FenError::InvalidSideToMove { offset: 20 });
+        test_fen_error("4k3/8/8/8/8/8/8/4K3 w KX - 0 1", FenError::InvalidCastling { offset: 23, found: 'X' });
+        test_fen_error("4k3/8/8/8/8/8/8/4K3 w - e9 0 1", FenError::InvalidEnPassant { offset: 24 });
+        test_fen_error("4k3/8/8/8/8/8/8/4K3 w - - x 1", FenError::InvalidNumber { field: FenField::HalfmoveClock, offset: 26 });
+        test_fen_error("4k3/8/8/8/8/8/8/4K3 w - - 0 -1", FenError::InvalidNumber { field: FenField::FullmoveNumber, offset: 28 });
+    }
+
+    #[test]
+    fn test_fen_error_display() {
+        let err = Chessboard::from_fen("4k3/8/8/8/8/8/8/4F3 w - - 0 1").unwrap_err();
+        assert_eq!(err.field(), Some(FenField::PiecePlacement));
+        assert_eq!(err.offset(), 17);
+        assert_eq!(err.to_string(), "invalid piece 'F' at offset 17");
+    }
+
+    #[test]
+    fn test_to_fen() {
+        let chessboard = Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 3".to_string());
+        assert_eq!(chessboard.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        let chessboard = Chessboard::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+        assert_eq!(chessboard.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn test_to_fen_en_passant() {
+        let mut chessboard = Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
+        let mv = chessboard.generate_move_from_string("e2e4".to_string());
+        chessboard.make_move(&mv);
+        assert_eq!(chessboard.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        assert_eq!(chessboard.to_fen_capturable_ep(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+
+        let chessboard = Chessboard::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3").unwrap();
+        assert_eq!(chessboard.to_fen_capturable_ep(), "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3");
+    }
+
+    #[test]
+    fn test_fen_chess960_castling() {
+        // X-FEN letters refer to the outermost rooks.
+        let chessboard = Chessboard::from_fen("rk2r3/8/8/8/8/8/8/1R2K1RR w KQkq - 0 1").unwrap();
+        assert_eq!(chessboard.white_castle, (Some(File::H), Some(File::B)));
+        assert_eq!(chessboard.black_castle, (Some(File::E), Some(File::A)));
+        assert!(chessboard.chess960);
+
+        // Shredder-FEN gives the files directly.
+        let chessboard = Chessboard::from_fen("rk2r3/8/8/8/8/8/8/1R2K1RR w GBe - 0 1").unwrap();
+        assert_eq!(chessboard.white_castle, (Some(File::G), Some(File::B)));
+        assert_eq!(chessboard.black_castle, (Some(File::E), None));
+        assert_eq!(chessboard.to_fen(), "rk2r3/8/8/8/8/8/8/1R2K1RR w GQk - 0 1");
+
+        let chessboard = Chessboard::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1").unwrap();
+        assert_eq!(chessboard.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert!(!chessboard.chess960);
+
+        let chessboard = Chessboard::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap();
+        assert_eq!(chessboard.to_fen(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+        assert!(chessboard.chess960);
+    }
+}
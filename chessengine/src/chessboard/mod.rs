@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use bitboard::patterns::pawn;
+
 use utils::color::Color;
 use utils::piece::Piece;
 
@@ -7,273 +9,84 @@ use utils::rank::Rank;
 use utils::file::File;
 use utils::square::Square;
 
-use self::moves::Move;
+use self::moves::GenType;
+use self::outcome::MaterialRule;
 
+pub mod attacks;
+pub mod check;
+pub mod epd;
+pub mod fen;
 pub mod moves;
+pub mod outcome;
 pub mod perft;
+#[cfg(test)]
+mod random_games;
+pub mod san;
+pub mod transform;
+pub mod validation;
+pub mod zobrist;
+
+// Castling rights of one side, stored as the files of the rooks that may
+// still castle: (king side, queen side).
+pub type CastlingRights = (Option<File>, Option<File>);
+
+// Everything make_move cannot recompute when undoing a move, saved once per
+// ply. The record also gives the game history to repetition detection.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct StateInfo {
+    pub white_castle: CastlingRights,
+    pub black_castle: CastlingRights,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: u8,
+    // Piece taken by the move played from this position.
+    pub captured: Option<Piece>,
+    pub hash: u64,
+    pub checkers: u64,
+}
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Chessboard {
     piece_board: Vec<u64>,
     color_board: Vec<u64>,
+    // Piece on each square, kept in sync with the bitboards for O(1) lookups.
+    mailbox: [Option<(Piece, Color)>; 64],
     pub empty_board: u64,
     pub turn: Color,
-    pub white_castle: (bool, bool),
-    pub black_castle: (bool, bool),
+    pub white_castle: CastlingRights,
+    pub black_castle: CastlingRights,
+    pub chess960: bool,
+    pub material_rule: MaterialRule,
     pub en_passant: Option<Square>,
     pub halfmove_clock: u8,
     pub fullmove_number: u16,
     pub perft_depth: u8,
-    wc_stack: Vec<(bool, bool)>,
-    bc_stack: Vec<(bool, bool)>,
-    ep_stack: Vec<Option<Square>>,
-    hm_stack: Vec<u8>,
+    history: Vec<StateInfo>,
     pub mv_hashmap: HashMap<String, u32>,
-    pub is_checkmate: bool,
-    pub is_stalemate: bool,
+    hash: u64,
+    checkers: u64,
 }
 
 impl Chessboard {
+    // Parse a FEN optionally followed by a perft depth, panicking on
+    // malformed input. Use `from_fen` for input that cannot be trusted.
     pub fn new(perft_string: String) -> Chessboard {
-        let perft_parts: Vec<&str> = perft_string.split(' ').collect();
-
-        // Parse the piece board.
-        let piece_placement = perft_parts[0];
-        let mut piece_board = Vec::new();
-        piece_board.push(0);
-        piece_board.push(0);
-        piece_board.push(0);
-        piece_board.push(0);
-        piece_board.push(0);
-        piece_board.push(0);
-        piece_board.push(0);
-        let mut color_board = Vec::new();
-        color_board.push(0);
-        color_board.push(0);
-        let mut empty_board = 0xff_ff_ff_ff_ff_ff_ff_ff;
-        let mut rank = Rank::Eight;
-        let mut file = File::A;
-
-        for c in piece_placement.chars() {
-            if c.is_numeric() {
-                let skip_count = c.to_digit(10).unwrap() as u32;
-                let new_file = file as u32 + skip_count;
-                if new_file == 8 {
-                    continue;
-                }
-                file = File::from_u32(new_file);
-            } else {
-                let piece = match c.to_ascii_lowercase() {
-                    'p' => Piece::Pawn,
-                    'n' => Piece::Knight,
-                    'b' => Piece::Bishop,
-                    'r' => Piece::Rook,
-                    'q' => Piece::Queen,
-                    'k' => Piece::King,
-                    '/' => {
-                        rank = Rank::from_u32(rank as u32 - 1);
-                        file = File::A;
-                        continue;
-                    },
-                    _ => panic!("Invalid piece {} {message}", "fancy", message = c),
-                };
-
-                let color = if c.is_ascii_lowercase() {
-                    Color::Black
-                } else {
-                    Color::White
-                };
-
-                piece_board[piece.to_usize()] |= 1 << (rank as u32 * 8 + file as u32);
-                color_board[color.to_usize()] |= 1 << (rank as u32 * 8 + file as u32);
-                empty_board &= !(1 << (rank as u32 * 8 + file as u32));
-
-                if file != File::H {
-                    file = File::from_u32(file as u32 + 1);
-                }
-            }
-        }
-
-        // Parse the turn board.
-        let turn = match perft_parts[1] {
-            "w" => Color::White,
-            "b" => Color::Black,
-            _ => panic!("Invalid turn"),
-        };
-
-        // Parse castling rights.
-        let white_castle = (
-            perft_parts[2].contains('K'),
-            perft_parts[2].contains('Q'),
-        );
-
-        let black_castle = (
-            perft_parts[2].contains('k'),
-            perft_parts[2].contains('q'),
-        );
-
-        // Parse the en passant square.
-        let en_passant = if perft_parts[3] == "-" {
-            None
-        } else {
-            let file = File::from_char(perft_parts[3].chars().nth(0).unwrap());
-            let rank = Rank::from_char(perft_parts[3].chars().nth(1).unwrap());
-            Some(Square::new(file, rank))
-        };
-
-        // Parse the halfmove clock.
-        let halfmove_clock = perft_parts[4].parse().unwrap();
-
-        // Parse the fullmove number.
-        let fullmove_number = perft_parts[5].parse().unwrap();
+        let mut fields: Vec<&str> = perft_string.split_whitespace().collect();
 
         // Parse the perft depth.
         // If no depth is given, default to 0.
-        let perft_depth: u8 = if perft_parts.len() > 6 {
-            let depth = perft_parts[6].replace("\n", "");
-            depth.parse().unwrap()
+        let perft_depth: u8 = if fields.len() > 6 {
+            let depth = fields.pop().unwrap();
+            depth.parse().unwrap_or_else(|_| panic!("Invalid perft depth {}", depth))
         } else {
             0
         };
 
-        let wc_stack = Vec::new();
-        let bc_stack = Vec::new();
-        let ep_stack = Vec::new();
-        let hm_stack = Vec::new();
-        let mv_hashmap = HashMap::new();
-        let is_checkmate = false;
-        let is_stalemate = false;
-
-        Chessboard {
-            piece_board,
-            color_board,
-            empty_board,
-            turn,
-            white_castle,
-            black_castle,
-            en_passant,
-            halfmove_clock,
-            fullmove_number,
-            perft_depth,
-            wc_stack,
-            bc_stack,
-            ep_stack,
-            hm_stack,
-            mv_hashmap,
-            is_checkmate,
-            is_stalemate,
-        }
-    }
-
-    pub fn to_fen(&self) {
-        let mut fen = String::new();
-        let mut empty_count = 0;
-        let mut rank = Rank::Eight;
-        let mut file = File::A;
-
-        for _ in 0..8 {
-            for _ in 0..8 {
-                let square = Square::new(file.clone(), rank.clone());
-                let piece = self.get_piece(&square);
-                let color = self.get_color(&square);
-
-                if piece == None {
-                    empty_count += 1;
-                } else {
-                    if empty_count > 0 {
-                        fen.push_str(&empty_count.to_string());
-                        empty_count = 0;
-                    }
-
-                    let piece_char = match piece.unwrap() {
-                        Piece::Pawn => "p",
-                        Piece::Knight => "n",
-                        Piece::Bishop => "b",
-                        Piece::Rook => "r",
-                        Piece::Queen => "q",
-                        Piece::King => "k",
-                    };
-
-                    let piece = match color.unwrap() {
-                        Color::White => piece_char.to_uppercase(),
-                        Color::Black => piece_char.to_lowercase(),
-                    };
-
-                    fen.push_str(&piece);
-                }
-
-                if file != File::H {
-                    file = File::from_u32(file as u32 + 1);
-                }
-            }
-
-            if empty_count > 0 {
-                fen.push_str(&empty_count.to_string());
-                empty_count = 0;
-            }
-
-            if rank != Rank::One {
-                fen.push('/');
-            }
-
-            if rank != Rank::One {
-                rank = Rank::from_u32(rank as u32 - 1);
-            }
-            file = File::A;
-        }
-
-        fen.push(' ');
-
-        let turn_char = match self.turn {
-            Color::White => "w",
-            Color::Black => "b",
-        };
-
-        fen.push_str(turn_char);
-        fen.push(' ');
-
-        let mut castle_string = String::new();
-
-        if self.white_castle.0 {
-            castle_string.push('K');
-        }
-
-        if self.white_castle.1 {
-            castle_string.push('Q');
-        }
-
-        if self.black_castle.0 {
-            castle_string.push('k');
-        }
-
-        if self.black_castle.1 {
-            castle_string.push('q');
-        }
-
-        if castle_string == "" {
-            castle_string.push('-');
-        }
-
-        fen.push_str(&castle_string);
-        fen.push(' ');
-
-        let ep_string = match self.en_passant {
-            Some(square) => square.to_string(),
-            None => "-".to_string(),
-        };
+        let fen = fields.join(" ");
+        let mut chessboard = Chessboard::from_fen(&fen)
+            .unwrap_or_else(|err| panic!("Invalid FEN {}: {}", fen, err));
+        chessboard.perft_depth = perft_depth;
 
-        fen.push_str(&ep_string);
-        fen.push(' ');
-
-        fen.push_str(&self.halfmove_clock.to_string());
-        fen.push(' ');
-
-        fen.push_str(&self.fullmove_number.to_string());
-        fen.push(' ');
-
-        fen.push('1');
-
-        println!("{}", fen);
+        chessboard
     }
 
     pub fn pretty_print(&self) {
@@ -331,66 +144,123 @@ impl Chessboard {
     }
 
     pub fn get_piece(&self, square: &Square) -> Option<Piece> {
-        if self.get_pieces(&Piece::Pawn) & square.to_bitboard() != 0 {
-            Some(Piece::Pawn)
-        } else if self.get_pieces(&Piece::Knight) & square.to_bitboard() != 0 {
-            Some(Piece::Knight)
-        } else if self.get_pieces(&Piece::Bishop) & square.to_bitboard() != 0 {
-            Some(Piece::Bishop)
-        } else if self.get_pieces(&Piece::Rook) & square.to_bitboard() != 0 {
-            Some(Piece::Rook)
-        } else if self.get_pieces(&Piece::Queen) & square.to_bitboard() != 0 {
-            Some(Piece::Queen)
-        } else if self.get_pieces(&Piece::King) & square.to_bitboard() != 0 {
-            Some(Piece::King)
-        } else {
-            None
-        }
+        self.mailbox[square.to_u32() as usize].map(|(piece, _)| piece)
     }
 
-    pub fn get_color(&self, square: &Square) -> Option<Color> {
-        if self.get_colors(&Color::White) & square.to_bitboard() != 0 {
-            Some(Color::White)
-        } else if self.get_colors(&Color::Black) & square.to_bitboard() != 0 {
-            Some(Color::Black)
-        } else {
-            None
+    // Piece and colour the bitboards hold on a square.
+    fn bitboards_at(&self, sq: usize) -> (Option<Piece>, Option<Color>) {
+        let bb = 1u64 << sq;
+        let piece = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen, Piece::King]
+            .into_iter()
+            .find(|piece| self.get_pieces(piece) & bb != 0);
+        let color = [Color::White, Color::Black]
+            .into_iter()
+            .find(|color| self.get_colors(color) & bb != 0);
+
+        (piece, color)
+    }
+
+    // Fill the mailbox from the bitboards.
+    fn rebuild_mailbox(&mut self) {
+        for sq in 0..64 {
+            let (piece, color) = self.bitboards_at(sq);
+            self.mailbox[sq] = piece.zip(color);
         }
     }
 
-    fn is_attacked(&self, pos: u64, color: &Color, enemies: u64, gen: fn(u64, &Chessboard, &Color, &mut Vec<Move>)) -> bool {
-        let moves = &mut Vec::new();
-        gen(pos, &self, color, moves);
+    // Check the mailbox against the bitboards, one square at a time.
+    pub fn is_mailbox_consistent(&self) -> bool {
+        (0..64).all(|sq| {
+            let expected = match self.bitboards_at(sq) {
+                (Some(piece), Some(color)) => Some((piece, color)),
+                (None, None) => None,
+                _ => return false,
+            };
+
+            self.mailbox[sq] == expected && (self.empty_board & (1u64 << sq) == 0) == expected.is_some()
+        })
+    }
 
-        for mv in moves.iter() {
-            if enemies & mv.to.to_bitboard() != 0 {
-                return true;
-            }
+    pub fn castling_rights(&self, color: &Color) -> CastlingRights {
+        match color {
+            Color::White => self.white_castle,
+            Color::Black => self.black_castle,
         }
+    }
 
-        return false;
+    fn castling_rights_mut(&mut self, color: &Color) -> &mut CastlingRights {
+        match color {
+            Color::White => &mut self.white_castle,
+            Color::Black => &mut self.black_castle,
+        }
+    }
+
+    fn put_piece(&mut self, piece: &Piece, color: &Color, square: &Square) {
+        let bb = square.to_bitboard();
+        self.piece_board[piece.to_usize()] |= bb;
+        self.color_board[color.to_usize()] |= bb;
+        self.empty_board &= !bb;
+        self.mailbox[square.to_u32() as usize] = Some((*piece, *color));
+        self.hash ^= zobrist::piece_key(piece, color, square);
+    }
+
+    fn remove_piece(&mut self, piece: &Piece, color: &Color, square: &Square) {
+        let bb = square.to_bitboard();
+        self.piece_board[piece.to_usize()] &= !bb;
+        self.color_board[color.to_usize()] &= !bb;
+        self.empty_board |= bb;
+        self.mailbox[square.to_u32() as usize] = None;
+        self.hash ^= zobrist::piece_key(piece, color, square);
+    }
+
+    // Whether a pawn of the side to move attacks the en passant square.
+    pub fn is_en_passant_capturable(&self) -> bool {
+        let square = match self.en_passant {
+            Some(square) => square,
+            None => return false,
+        };
+
+        let opposite = self.turn.opposite();
+        let attackers = pawn::west_attack_targets(square.to_bitboard(), &opposite)
+            | pawn::east_attack_targets(square.to_bitboard(), &opposite);
+
+        attackers & self.get_pieces_color(&Piece::Pawn, &self.turn) != 0
+    }
+
+    pub fn get_color(&self, square: &Square) -> Option<Color> {
+        self.mailbox[square.to_u32() as usize].map(|(_, color)| color)
     }
 
     pub fn is_attacked_square(&self, square: u64, color: &Color) -> bool {
+        if square == 0 {
+            return false;
+        }
+
+        let square = Square::from_u32(square.trailing_zeros());
         let opposite = self.get_opposite_color(color);
 
-        let knights = self.get_pieces_color(&Piece::Knight, &opposite);
-        let bishops = self.get_pieces_color(&Piece::Bishop, &opposite);
-        let rooks = self.get_pieces_color(&Piece::Rook, &opposite);
-        let queens = self.get_pieces_color(&Piece::Queen, &opposite);
-        let king = self.get_pieces_color(&Piece::King, &opposite);
+        self.attackers_to(&square, !self.empty_board) & self.get_colors(&opposite) != 0
+    }
 
-        let pawns = self.get_pieces_color(&Piece::Pawn, &opposite);
-        let targets = moves::piece::pawn::generate_capture_moves(square, &pawns, &self, color);
-        if targets.len() > 0 {
-            return true;
+    pub fn checkers(&self) -> u64 {
+        self.checkers
+    }
+
+    // Enemy pieces giving check to the side to move.
+    pub(super) fn compute_checkers(&self) -> u64 {
+        self.checkers_of(&self.turn)
+    }
+
+    // Enemy pieces giving check to the king of `color`.
+    fn checkers_of(&self, color: &Color) -> u64 {
+        let king = self.get_pieces_color(&Piece::King, color);
+        if king == 0 {
+            return 0;
         }
 
-        self.is_attacked(square, color, knights, moves::piece::knight::generate_pseudo_moves) ||
-        self.is_attacked(square, color, bishops, moves::piece::sliding_piece::bishop::generate_pseudo_moves) ||
-        self.is_attacked(square, color, rooks, moves::piece::sliding_piece::rook::generate_pseudo_moves) ||
-        self.is_attacked(square, color, queens, moves::piece::sliding_piece::queen::generate_pseudo_moves) ||
-        self.is_attacked(square, color, king, moves::piece::king::generate_pseudo_moves)
+        let square = Square::from_u32(king.trailing_zeros());
+
+        self.attackers_to(&square, !self.empty_board) & self.get_colors(&color.opposite())
     }
 
     pub fn is_making_check(&self, color: &Color) -> bool {
@@ -400,6 +270,10 @@ impl Chessboard {
     }
 
     pub fn is_in_check(&self, color: &Color) -> bool {
+        if *color == self.turn {
+            return self.checkers != 0;
+        }
+
         let king = self.get_pieces_color(&Piece::King, color);
 
         self.is_attacked_square(king, color)
@@ -413,24 +287,63 @@ impl Chessboard {
         let mut copy = self.clone();
         if *color != copy.turn {
             copy.turn = *color;
+            copy.hash ^= zobrist::side_key();
+            copy.checkers = copy.compute_checkers();
         }
 
-        let moves = copy.generate_legal_moves();
+        let moves = copy.generate_legal_moves(GenType::All);
         moves.len() == 0
     }
 
-    pub fn push(&mut self) {
-        self.wc_stack.push(self.white_castle);
-        self.bc_stack.push(self.black_castle);
-        self.ep_stack.push(self.en_passant);
-        self.hm_stack.push(self.halfmove_clock);
+    // Number of earlier occurrences of the current position. Only positions
+    // since the last capture or pawn move, with the same side to move, are
+    // looked at.
+    pub fn repetition_count(&self) -> usize {
+        self.history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|state| state.hash == self.hash)
+            .count()
+    }
+
+    pub fn is_repetition(&self) -> bool {
+        self.repetition_count() >= 2
+    }
+
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
+
+    pub fn history(&self) -> &[StateInfo] {
+        &self.history
     }
 
-    pub fn pop(&mut self) {
-        self.white_castle = self.wc_stack.pop().unwrap();
-        self.black_castle = self.bc_stack.pop().unwrap();
-        self.en_passant = self.ep_stack.pop().unwrap();
-        self.halfmove_clock = self.hm_stack.pop().unwrap();
+    pub fn push(&mut self, captured: Option<Piece>) {
+        self.history.push(StateInfo {
+            white_castle: self.white_castle,
+            black_castle: self.black_castle,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            captured,
+            hash: self.hash,
+            checkers: self.checkers,
+        });
+    }
+
+    pub fn pop(&mut self) -> StateInfo {
+        let state = self.history.pop().unwrap();
+
+        self.white_castle = state.white_castle;
+        self.black_castle = state.black_castle;
+        self.en_passant = state.en_passant;
+        self.halfmove_clock = state.halfmove_clock;
+        self.hash = state.hash;
+        self.checkers = state.checkers;
+
+        state
     }
 }
 
@@ -438,9 +351,10 @@ impl Chessboard {
 mod tests {
     use std::assert_eq;
 
-    use utils::{color::Color, square::Square};
+    use utils::{color::Color, piece::Piece, square::Square};
 
     use super::Chessboard;
+    use super::moves::GenType;
 
     fn test_is_attacked_square(fen: &str, square: Square, color: &Color, expected: bool) {
         let chessboard = Chessboard::new(fen.to_string());
@@ -457,6 +371,13 @@ mod tests {
         assert_eq!(chessboard.is_making_check(color), expected);
     }
 
+    fn play(chessboard: &mut Chessboard, moves: &[&str]) {
+        for mv in moves {
+            let mv = chessboard.generate_move_from_string(mv.to_string());
+            chessboard.make_move(&mv);
+        }
+    }
+
     fn test_is_checkmate(fen: &str, color: &Color, expected: bool) {
         let chessboard = Chessboard::new(fen.to_string());
         assert_eq!(chessboard.is_checkmate(color), expected);
@@ -480,4 +401,118 @@ mod tests {
         test_is_checkmate("4k3/8/3rqr2/8/8/8/8/4K3 w - - 0 1 1", &Color::White, true);
         test_is_checkmate("4k3/8/3rqr2/8/8/8/8/4K3 b - - 0 1 1", &Color::Black, false);
     }
+
+    #[test]
+    fn test_halfmove_clock() {
+        let mut chessboard = Chessboard::new("r3k2r/8/8/8/8/8/4P3/R3K2R w KQkq - 7 1".to_string());
+        play(&mut chessboard, &["a1b1"]);
+        assert_eq!(chessboard.halfmove_clock, 8);
+        play(&mut chessboard, &["e8g8"]);
+        assert_eq!(chessboard.halfmove_clock, 9);
+        play(&mut chessboard, &["e2e4"]);
+        assert_eq!(chessboard.halfmove_clock, 0);
+        play(&mut chessboard, &["a8a2"]);
+        assert_eq!(chessboard.halfmove_clock, 1);
+        play(&mut chessboard, &["b1b8"]);
+        assert_eq!(chessboard.halfmove_clock, 2);
+        play(&mut chessboard, &["f8b8"]);
+        assert_eq!(chessboard.halfmove_clock, 0);
+
+        let mv = chessboard.generate_move_from_string("e1f1".to_string());
+        chessboard.make_move(&mv);
+        assert_eq!(chessboard.halfmove_clock, 1);
+        chessboard.unmake_move(&mv);
+        assert_eq!(chessboard.halfmove_clock, 0);
+    }
+
+    #[test]
+    fn test_repetition() {
+        let mut chessboard = Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+        play(&mut chessboard, &shuffle);
+        assert_eq!(chessboard.repetition_count(), 1);
+        assert!(!chessboard.is_repetition());
+
+        play(&mut chessboard, &shuffle);
+        assert_eq!(chessboard.repetition_count(), 2);
+        assert!(chessboard.is_repetition());
+
+        // A pawn move makes every earlier position unreachable.
+        play(&mut chessboard, &["e2e4", "e7e5"]);
+        play(&mut chessboard, &shuffle);
+        assert_eq!(chessboard.repetition_count(), 1);
+    }
+
+    #[test]
+    fn test_fifty_move_draw() {
+        let mut chessboard = Chessboard::new("4k3/8/8/8/8/8/8/R3K3 w - - 99 80".to_string());
+        assert!(!chessboard.is_fifty_move_draw());
+        play(&mut chessboard, &["a1a2"]);
+        assert!(chessboard.is_fifty_move_draw());
+
+        let chessboard = Chessboard::new("4k3/8/8/8/8/8/8/R3K3 w - - 100 80".to_string());
+        assert!(chessboard.is_fifty_move_draw());
+    }
+
+    #[test]
+    fn test_mailbox() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut chessboard = Chessboard::new(fen.to_string());
+        assert!(chessboard.is_mailbox_consistent());
+        assert_eq!(chessboard.get_piece(&Square::from_string("e5")), Some(Piece::Knight));
+        assert_eq!(chessboard.get_color(&Square::from_string("e5")), Some(Color::White));
+        assert_eq!(chessboard.get_piece(&Square::from_string("e6")), Some(Piece::Pawn));
+        assert_eq!(chessboard.get_color(&Square::from_string("e6")), Some(Color::Black));
+        assert_eq!(chessboard.get_piece(&Square::from_string("c4")), None);
+        assert_eq!(chessboard.get_color(&Square::from_string("c4")), None);
+
+        for mv in chessboard.generate_legal_moves(GenType::All) {
+            chessboard.make_move(&mv);
+            assert!(chessboard.is_mailbox_consistent(), "Make {}", mv.to_string());
+            chessboard.unmake_move(&mv);
+            assert!(chessboard.is_mailbox_consistent(), "Unmake {}", mv.to_string());
+        }
+
+        // Castling, en passant and promotion.
+        play(&mut chessboard, &["e1g1", "c7c5", "d5c6", "h3g2", "a2a3", "g2f1q"]);
+        assert!(chessboard.is_mailbox_consistent());
+        assert_eq!(chessboard.get_piece(&Square::from_string("f1")), Some(Piece::Queen));
+        assert_eq!(chessboard.get_color(&Square::from_string("f1")), Some(Color::Black));
+        assert_eq!(chessboard.get_piece(&Square::from_string("c5")), None);
+    }
+
+    #[test]
+    fn test_state_history() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let mut chessboard = Chessboard::new(fen.to_string());
+        let start = chessboard.clone();
+
+        let mut moves = Vec::new();
+        for mv in ["e2e4", "d7d5", "e4d5", "d8d5", "f1b5"] {
+            let mv = chessboard.generate_move_from_string(mv.to_string());
+            chessboard.make_move(&mv);
+            moves.push(mv);
+        }
+
+        let history = chessboard.history();
+        assert_eq!(history.len(), moves.len());
+        assert_eq!(history[0].hash, start.hash());
+        assert_eq!(history[1].en_passant, Some(Square::from_string("e3")));
+        assert_eq!(history[2].captured, Some(Piece::Pawn));
+        assert_eq!(history[3].captured, Some(Piece::Pawn));
+        assert_eq!(history[4].captured, None);
+        assert_eq!(history[4].halfmove_clock, 0);
+
+        // Bb5+ is check, from the bishop on b5.
+        assert_eq!(chessboard.checkers(), Square::from_string("b5").to_bitboard());
+        assert!(chessboard.is_in_check(&Color::Black));
+
+        for mv in moves.iter().rev() {
+            chessboard.unmake_move(mv);
+        }
+        assert_eq!(chessboard.to_fen(), fen);
+        assert_eq!(chessboard.hash(), start.hash());
+        assert!(chessboard.history().is_empty());
+    }
 }
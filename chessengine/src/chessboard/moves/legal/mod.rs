@@ -0,0 +1,405 @@
+use bitboard::attacks::{KING_ATTACKS, PAWN_ATTACKS};
+
+use utils::color::Color;
+use utils::piece::Piece;
+use utils::rank::{Rank, RANKS};
+use utils::square::Square;
+
+use crate::chessboard::Chessboard;
+use crate::chessboard::attacks::{attacks_from, between};
+
+use super::{piece, GenType, Move, MoveList, PROMOTION_PIECES};
+
+impl Chessboard {
+    // Moves that do not leave the king in check, of the kind asked for as
+    // with `generate_moves`. Checkers and pins are resolved up front so that
+    // no move has to be played to be tested.
+    pub fn generate_legal_moves(&self, gen: GenType) -> MoveList {
+        let king = self.get_pieces_color(&Piece::King, &self.turn);
+
+        // Without a king every move is legal.
+        if king == 0 {
+            return self.generate_moves(gen);
+        }
+
+        let mut moves = MoveList::new();
+        let king_square = Square::from_u32(king.trailing_zeros());
+        let occupancy = !self.empty_board;
+        let allies = self.get_colors(&self.turn);
+        let enemies = self.get_colors(&self.turn.opposite());
+        // The check mask below already keeps the other pieces to evasions.
+        let gen_targets = match gen {
+            GenType::All | GenType::Evasions => !allies,
+            GenType::Captures => enemies,
+            GenType::Quiets => self.empty_board,
+        };
+
+        // The king is taken out of the occupancy so that it does not hide
+        // from a slider behind its own square.
+        let mut targets = KING_ATTACKS[king_square.to_u32() as usize] & gen_targets;
+        while targets != 0 {
+            let to = Square::from_u32(targets.trailing_zeros());
+
+            if self.attackers_to(&to, occupancy ^ king) & enemies == 0 {
+                moves.push(Move::new(king_square, to, capture_flag(enemies, &to)));
+            }
+
+            targets &= targets - 1;
+        }
+
+        // In double check only the king can move.
+        if self.checkers.count_ones() > 1 {
+            return moves;
+        }
+
+        let check_mask = self.check_mask(&self.turn);
+        let pin_rays = self.pin_rays(&king_square, occupancy, allies);
+
+        for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+            let mut pieces = self.get_pieces_color(&piece, &self.turn);
+
+            while pieces != 0 {
+                let from = Square::from_u32(pieces.trailing_zeros());
+                let mut targets = attacks_from(&piece, &self.turn, &from, occupancy)
+                    & gen_targets & check_mask & pin_rays[from.to_u32() as usize];
+
+                while targets != 0 {
+                    let to = Square::from_u32(targets.trailing_zeros());
+                    moves.push(Move::new(from, to, capture_flag(enemies, &to)));
+                    targets &= targets - 1;
+                }
+
+                pieces &= pieces - 1;
+            }
+        }
+
+        self.generate_legal_pawn_moves(&king_square, gen, check_mask, &pin_rays, &mut moves);
+
+        if self.checkers == 0 && matches!(gen, GenType::All | GenType::Quiets) {
+            moves.extend(piece::king::generate_castling_moves(self, &self.turn));
+        }
+
+        moves
+    }
+
+    // Squares other pieces than the king of `color` must move to when in
+    // check: capturing the checker or blocking the check. Nothing helps in
+    // double check. Only the checkers of the side to move are kept.
+    pub fn check_mask(&self, color: &Color) -> u64 {
+        let king = self.get_pieces_color(&Piece::King, color);
+        let checkers = match *color == self.turn {
+            true => self.checkers,
+            false => self.checkers_of(color),
+        };
+
+        match checkers.count_ones() {
+            0 => !0,
+            1 => checkers | between(&Square::from_u32(king.trailing_zeros()), &Square::from_u32(checkers.trailing_zeros())),
+            _ => 0,
+        }
+    }
+
+    // For each square, the squares its piece may move to without exposing
+    // the king: the ray up to the pinning piece for pinned pieces, the whole
+    // board otherwise.
+    fn pin_rays(&self, king_square: &Square, occupancy: u64, allies: u64) -> [u64; 64] {
+        let mut pin_rays = [!0u64; 64];
+        let opposite = self.turn.opposite();
+        let queens = self.get_pieces_color(&Piece::Queen, &opposite);
+
+        let mut snipers = (attacks_from(&Piece::Bishop, &self.turn, king_square, 0)
+            & (self.get_pieces_color(&Piece::Bishop, &opposite) | queens))
+            | (attacks_from(&Piece::Rook, &self.turn, king_square, 0)
+            & (self.get_pieces_color(&Piece::Rook, &opposite) | queens));
+
+        while snipers != 0 {
+            let sniper = Square::from_u32(snipers.trailing_zeros());
+            let ray = between(king_square, &sniper);
+            let blockers = ray & occupancy;
+
+            if blockers.count_ones() == 1 && blockers & allies != 0 {
+                pin_rays[blockers.trailing_zeros() as usize] = ray | sniper.to_bitboard();
+            }
+
+            snipers &= snipers - 1;
+        }
+
+        pin_rays
+    }
+
+    fn generate_legal_pawn_moves(
+        &self,
+        king_square: &Square,
+        gen: GenType,
+        check_mask: u64,
+        pin_rays: &[u64; 64],
+        moves: &mut MoveList,
+    ) {
+        let enemies = self.get_colors(&self.turn.opposite());
+        let (start_rank, promotion_rank) = match self.turn {
+            Color::White => (Rank::Two, Rank::Eight),
+            Color::Black => (Rank::Seven, Rank::One),
+        };
+
+        // Pushes promoting to a piece go with the captures.
+        let (push_targets, capture_targets) = match gen {
+            GenType::All | GenType::Evasions => (!0, enemies),
+            GenType::Captures => (RANKS[promotion_rank as usize], enemies),
+            GenType::Quiets => (!RANKS[promotion_rank as usize], 0),
+        };
+
+        let mut pawns = self.get_pieces_color(&Piece::Pawn, &self.turn);
+        while pawns != 0 {
+            let from = Square::from_u32(pawns.trailing_zeros());
+            let allowed = check_mask & pin_rays[from.to_u32() as usize];
+
+            let single = match self.turn {
+                Color::White => from.to_bitboard() << 8,
+                Color::Black => from.to_bitboard() >> 8,
+            } & self.empty_board;
+            let double = match (self.turn, from.rank == start_rank) {
+                (Color::White, true) => single << 8,
+                (Color::Black, true) => single >> 8,
+                _ => 0,
+            } & self.empty_board;
+
+            let mut targets = ((single & push_targets)
+                | (PAWN_ATTACKS[self.turn.to_usize()][from.to_u32() as usize] & capture_targets)) & allowed;
+            while targets != 0 {
+                let to = Square::from_u32(targets.trailing_zeros());
+                let mv = Move::new(from, to, capture_flag(enemies, &to));
+
+                match to.rank == promotion_rank {
+                    true => moves.extend(PROMOTION_PIECES.iter().map(|piece| mv.with_promotion(piece))),
+                    false => moves.push(mv),
+                }
+
+                targets &= targets - 1;
+            }
+
+            if double & push_targets & allowed != 0 {
+                moves.push(Move::new(from, Square::from_u32(double.trailing_zeros()), Move::DOUBLE_PUSH));
+            }
+
+            if let Some(mv) = self.legal_en_passant(&from, king_square).filter(|_| gen != GenType::Quiets) {
+                moves.push(mv);
+            }
+
+            pawns &= pawns - 1;
+        }
+    }
+
+    // En passant removes two pieces from the same rank, which pins cannot
+    // describe, so the resulting occupancy is checked directly.
+    fn legal_en_passant(&self, from: &Square, king_square: &Square) -> Option<Move> {
+        let to = self.en_passant?;
+
+        if PAWN_ATTACKS[self.turn.to_usize()][from.to_u32() as usize] & to.to_bitboard() == 0 {
+            return None;
+        }
+
+        let captured = Square::new(to.file, from.rank).to_bitboard();
+        let occupancy = !self.empty_board ^ from.to_bitboard() ^ to.to_bitboard() ^ captured;
+        let enemies = self.get_colors(&self.turn.opposite()) & !captured;
+
+        match self.attackers_to(king_square, occupancy) & enemies {
+            0 => Some(Move::new(*from, to, Move::EN_PASSANT)),
+            _ => None,
+        }
+    }
+
+    // Whether the move could be generated in this position, leaving aside
+    // checks. Castling is only pseudo legal when it is legal.
+    pub fn is_pseudo_legal(&self, mv: &Move) -> bool {
+        let from = mv.from();
+        let to = mv.to();
+
+        if self.get_color(&from) != Some(self.turn) {
+            return false;
+        }
+
+        if mv.is_castling() {
+            return piece::king::generate_castling_moves(self, &self.turn).contains(mv);
+        }
+
+        let piece = self.get_piece(&from).unwrap();
+        let enemies = self.get_colors(&self.turn.opposite());
+        let capture = enemies & to.to_bitboard() != 0;
+
+        if piece != Piece::Pawn {
+            return matches!(mv.flags(), Move::QUIET | Move::CAPTURE)
+                && mv.is_capture() == capture
+                && attacks_from(&piece, &self.turn, &from, !self.empty_board) & !self.get_colors(&self.turn) & to.to_bitboard() != 0;
+        }
+
+        let (start_rank, promotion_rank) = match self.turn {
+            Color::White => (Rank::Two, Rank::Eight),
+            Color::Black => (Rank::Seven, Rank::One),
+        };
+        let single = match self.turn {
+            Color::White => from.to_bitboard() << 8,
+            Color::Black => from.to_bitboard() >> 8,
+        } & self.empty_board;
+        let attacks = PAWN_ATTACKS[self.turn.to_usize()][from.to_u32() as usize];
+
+        if mv.is_en_passant() {
+            return self.en_passant == Some(to) && attacks & to.to_bitboard() != 0;
+        }
+
+        if mv.is_double_push() {
+            let double = match self.turn {
+                Color::White => single << 8,
+                Color::Black => single >> 8,
+            } & self.empty_board;
+            return from.rank == start_rank && double & to.to_bitboard() != 0;
+        }
+
+        let targets = match mv.is_capture() {
+            true => attacks & enemies,
+            false => single,
+        };
+
+        matches!(mv.flags(), Move::QUIET | Move::CAPTURE) != mv.is_promotion()
+            && mv.is_promotion() == (to.rank == promotion_rank)
+            && targets & to.to_bitboard() != 0
+    }
+
+    // Whether the move is pseudo legal and does not leave the king in check.
+    pub fn is_legal(&self, mv: &Move) -> bool {
+        if !self.is_pseudo_legal(mv) {
+            return false;
+        }
+
+        let king = self.get_pieces_color(&Piece::King, &self.turn);
+        if king == 0 || mv.is_castling() {
+            return true;
+        }
+
+        // Play the move on the occupancy only, and look for attackers of the
+        // king that the move did not take.
+        let captured = Chessboard::captured_square(mv).to_bitboard();
+        let occupancy = (!self.empty_board ^ mv.from().to_bitboard() ^ (captured & !mv.to().to_bitboard()))
+            | mv.to().to_bitboard();
+        let king_square = match king & mv.from().to_bitboard() {
+            0 => Square::from_u32(king.trailing_zeros()),
+            _ => mv.to(),
+        };
+        let enemies = self.get_colors(&self.turn.opposite()) & !captured;
+
+        self.attackers_to(&king_square, occupancy) & enemies == 0
+    }
+}
+
+fn capture_flag(enemies: u64, to: &Square) -> u16 {
+    match enemies & to.to_bitboard() {
+        0 => Move::QUIET,
+        _ => Move::CAPTURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessboard::random_games::play_random_games;
+
+    // Reference generator: pseudo legal moves filtered by playing them.
+    fn filtered_pseudo_legal_moves(chessboard: &mut Chessboard) -> MoveList {
+        let mut moves = chessboard.generate_moves(GenType::All);
+        moves.extend(piece::king::generate_castling_moves(chessboard, &chessboard.turn));
+        moves.retain(|mv| {
+            chessboard.make_move(mv);
+            let legal = !chessboard.is_making_check(&chessboard.turn);
+            chessboard.unmake_move(mv);
+            legal
+        });
+        moves.sort();
+        moves
+    }
+
+    fn compare_generators(chessboard: &mut Chessboard, depth: u8) {
+        let mut legal = chessboard.generate_legal_moves(GenType::All);
+        legal.sort();
+        assert_eq!(legal.to_vec(), filtered_pseudo_legal_moves(chessboard).to_vec(), "{}", chessboard.to_fen());
+
+        if depth > 1 {
+            for mv in legal {
+                chessboard.make_move(&mv);
+                compare_generators(chessboard, depth - 1);
+                chessboard.unmake_move(&mv);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_legal_moves() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        ];
+
+        for fen in fens {
+            let mut chessboard = Chessboard::new(fen.to_string());
+            compare_generators(&mut chessboard, 2);
+        }
+    }
+
+    #[test]
+    fn test_legal_en_passant() {
+        // Taking en passant would expose the king along the rank.
+        let chessboard = Chessboard::new("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1".to_string());
+        assert!(!chessboard.generate_legal_moves(GenType::All).iter().any(|mv| mv.is_en_passant()));
+
+        // The pawn giving check can be taken en passant.
+        let chessboard = Chessboard::new("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1".to_string());
+        assert!(chessboard.generate_legal_moves(GenType::All).iter().any(|mv| mv.is_en_passant()));
+    }
+
+    #[test]
+    fn test_legal_evasions() {
+        // Double check: only the king moves.
+        let chessboard = Chessboard::new("4k3/8/8/8/1b6/8/4r3/R3K2R w KQ - 0 1".to_string());
+        assert!(chessboard.generate_legal_moves(GenType::All).iter().all(|mv| mv.from() == Square::from_string("e1")));
+
+        // A pinned rook may only slide along the pin.
+        let chessboard = Chessboard::new("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1".to_string());
+        let rook_moves: Vec<Move> = chessboard.generate_legal_moves(GenType::All).into_iter()
+            .filter(|mv| mv.from() == Square::from_string("e2"))
+            .collect();
+        assert_eq!(rook_moves.len(), 5);
+    }
+
+    // Random games, checking at each position the legal moves, the moves of
+    // the previous position and random moves against the generators, and
+    // the legal captures and quiets against the legal moves.
+    #[test]
+    fn test_is_legal_random_games() {
+        let mut previous = MoveList::new();
+
+        play_random_games(4, 80, 0x9e3779b97f4a7c15, |chessboard, legal, random| {
+            let mut pseudo = chessboard.generate_moves(GenType::All);
+            pseudo.extend(piece::king::generate_castling_moves(chessboard, &chessboard.turn));
+
+            let mut candidates = legal.clone();
+            candidates.extend(previous.iter().copied());
+            candidates.extend((0..64).map(|_| Move(random.next() as u16)));
+
+            for mv in candidates.iter() {
+                assert_eq!(chessboard.is_pseudo_legal(mv), pseudo.contains(mv), "{:?} in {}", mv, chessboard.to_fen());
+                assert_eq!(chessboard.is_legal(mv), legal.contains(mv), "{:?} in {}", mv, chessboard.to_fen());
+            }
+
+            let mut staged = chessboard.generate_legal_moves(GenType::Captures);
+            staged.extend(chessboard.generate_legal_moves(GenType::Quiets));
+            staged.sort();
+            let mut all = legal.clone();
+            all.sort();
+            assert_eq!(staged.to_vec(), all.to_vec(), "{}", chessboard.to_fen());
+
+            previous = legal.clone();
+        });
+    }
+}
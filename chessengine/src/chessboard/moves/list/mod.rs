@@ -0,0 +1,215 @@
+use std::fmt;
+use std::ops::Deref;
+
+use super::Move;
+
+// No legal position has more moves than that, the record being 218.
+pub const MAX_MOVES: usize = 256;
+
+// Moves kept on the stack, each with a score for move ordering.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    scores: [i32; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        MoveList {
+            moves: [Move(0); MAX_MOVES],
+            scores: [0; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        self.push_scored(mv, 0);
+    }
+
+    pub fn push_scored(&mut self, mv: Move, score: i32) {
+        self.moves[self.len] = mv;
+        self.scores[self.len] = score;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn score(&self, index: usize) -> i32 {
+        self.scores[..self.len][index]
+    }
+
+    pub fn set_score(&mut self, index: usize, score: i32) {
+        self.scores[..self.len][index] = score;
+    }
+
+    // Moves with their scores.
+    pub fn scored(&self) -> impl Iterator<Item = (Move, i32)> + '_ {
+        self.moves[..self.len].iter().copied().zip(self.scores[..self.len].iter().copied())
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&Move) -> bool) {
+        let mut kept = 0;
+
+        for i in 0..self.len {
+            if f(&self.moves[i]) {
+                self.moves[kept] = self.moves[i];
+                self.scores[kept] = self.scores[i];
+                kept += 1;
+            }
+        }
+
+        self.len = kept;
+    }
+
+    // Insertion sorts, fast enough on lists this short and they do not
+    // allocate. Both keep the order of equal entries.
+    pub fn sort(&mut self) {
+        self.sort_by(|a, b| a.0 > b.0);
+    }
+
+    // Best score first.
+    pub fn sort_by_score(&mut self) {
+        self.sort_by(|a, b| a.1 < b.1);
+    }
+
+    // `after(a, b)` tells if entry `a` goes after entry `b`.
+    fn sort_by(&mut self, after: impl Fn((Move, i32), (Move, i32)) -> bool) {
+        for i in 1..self.len {
+            let entry = (self.moves[i], self.scores[i]);
+            let mut j = i;
+
+            while j > 0 && after((self.moves[j - 1], self.scores[j - 1]), entry) {
+                self.moves[j] = self.moves[j - 1];
+                self.scores[j] = self.scores[j - 1];
+                j -= 1;
+            }
+
+            self.moves[j] = entry.0;
+            self.scores[j] = entry.1;
+        }
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> MoveList {
+        MoveList::new()
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl Extend<Move> for MoveList {
+    fn extend<I: IntoIterator<Item = Move>>(&mut self, iter: I) {
+        for mv in iter {
+            self.push(mv);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter { list: self, index: 0 }
+    }
+}
+
+pub struct IntoIter {
+    list: MoveList,
+    index: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        let mv = self.list.get(self.index).copied();
+        self.index += 1;
+        mv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use utils::square::Square;
+
+    fn mv(from: &str, to: &str) -> Move {
+        Move::new(Square::from_string(from), Square::from_string(to), Move::QUIET)
+    }
+
+    #[test]
+    fn test_move_list() {
+        let mut moves = MoveList::new();
+        assert!(moves.is_empty());
+
+        moves.push(mv("e2", "e4"));
+        moves.push_scored(mv("d2", "d4"), 50);
+        moves.extend([mv("g1", "f3"), mv("b1", "c3")]);
+        assert_eq!(moves.len(), 4);
+        assert_eq!(moves[1], mv("d2", "d4"));
+        assert!(moves.contains(&mv("g1", "f3")));
+        assert_eq!(moves.iter().count(), 4);
+        assert_eq!(moves.clone().into_iter().collect::<Vec<Move>>(), moves.to_vec());
+
+        moves.retain(|m| *m != mv("e2", "e4"));
+        assert_eq!(moves.to_vec(), vec![mv("d2", "d4"), mv("g1", "f3"), mv("b1", "c3")]);
+        assert_eq!(moves.score(0), 50);
+
+        moves.clear();
+        assert!(moves.is_empty());
+    }
+
+    #[test]
+    fn test_move_list_sort() {
+        let mut moves = MoveList::new();
+        moves.push_scored(mv("e2", "e4"), 10);
+        moves.push_scored(mv("a2", "a3"), 30);
+        moves.push_scored(mv("d2", "d4"), 10);
+        moves.push_scored(mv("g1", "f3"), -5);
+
+        moves.sort_by_score();
+        let scored: Vec<(Move, i32)> = moves.scored().collect();
+        assert_eq!(scored, vec![
+            (mv("a2", "a3"), 30),
+            (mv("e2", "e4"), 10),
+            (mv("d2", "d4"), 10),
+            (mv("g1", "f3"), -5),
+        ]);
+
+        moves.sort();
+        let mut expected = moves.to_vec();
+        expected.sort();
+        assert_eq!(moves.to_vec(), expected);
+        assert_eq!(moves.scored().find(|(m, _)| *m == mv("a2", "a3")).unwrap().1, 30);
+
+        moves.set_score(0, 7);
+        assert_eq!(moves.score(0), 7);
+    }
+}
@@ -1,66 +1,185 @@
+use std::fmt;
+
 use super::Chessboard;
+use super::zobrist;
 
 use utils::color::Color;
-use utils::direction::{NORT, SOUT};
-use utils::file::File;
+use utils::direction::NORT;
 use utils::piece::Piece;
 use utils::rank::Rank;
 use utils::square::Square;
 
+pub mod legal;
+pub mod list;
 pub mod piece;
 
-#[derive(Debug, PartialEq, Clone, Copy, Eq, PartialOrd, Ord)]
-pub struct Move {
-    pub from: Square,
-    pub to: Square,
-    pub promotion: Option<Piece>,
-    pub capture: Option<Piece>,
-}
+use self::list::MoveList;
+
+// A move packed in 16 bits: origin in bits 0-5, destination in bits 6-11
+// and flags in bits 12-15.
+//
+// Castling is stored as the king taking its own rook, which is unambiguous in
+// Chess960 as well.
+#[derive(PartialEq, Clone, Copy, Eq, PartialOrd, Ord, Hash)]
+pub struct Move(u16);
+
+const FROM_MASK: u16 = 0x3f;
+const TO_SHIFT: u16 = 6;
+const FLAGS_SHIFT: u16 = 12;
+
+const PROMOTION_PIECES: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
 
 impl Move {
-    pub fn new(from: Square, to: Square) -> Move {
-        Move {
-            from,
-            to,
-            promotion: None,
-            capture: None,
+    pub const QUIET: u16 = 0b0000;
+    pub const DOUBLE_PUSH: u16 = 0b0001;
+    pub const KING_CASTLE: u16 = 0b0010;
+    pub const QUEEN_CASTLE: u16 = 0b0011;
+    pub const CAPTURE: u16 = 0b0100;
+    pub const EN_PASSANT: u16 = 0b0101;
+    pub const KNIGHT_PROMOTION: u16 = 0b1000;
+    pub const BISHOP_PROMOTION: u16 = 0b1001;
+    pub const ROOK_PROMOTION: u16 = 0b1010;
+    pub const QUEEN_PROMOTION: u16 = 0b1011;
+    pub const KNIGHT_PROMOTION_CAPTURE: u16 = 0b1100;
+    pub const BISHOP_PROMOTION_CAPTURE: u16 = 0b1101;
+    pub const ROOK_PROMOTION_CAPTURE: u16 = 0b1110;
+    pub const QUEEN_PROMOTION_CAPTURE: u16 = 0b1111;
+
+    pub fn new(from: Square, to: Square, flags: u16) -> Move {
+        Move(from.to_u32() as u16 | (to.to_u32() as u16) << TO_SHIFT | flags << FLAGS_SHIFT)
+    }
+
+    pub fn castle(king: Square, rook: Square) -> Move {
+        let flags = match rook.file < king.file {
+            true => Move::QUEEN_CASTLE,
+            false => Move::KING_CASTLE,
+        };
+
+        Move::new(king, rook, flags)
+    }
+
+    // Same move promoting to the given piece, keeping the capture flag.
+    pub fn with_promotion(&self, piece: &Piece) -> Move {
+        let flags = match piece {
+            Piece::Knight => Move::KNIGHT_PROMOTION,
+            Piece::Bishop => Move::BISHOP_PROMOTION,
+            Piece::Rook => Move::ROOK_PROMOTION,
+            Piece::Queen => Move::QUEEN_PROMOTION,
+            _ => panic!("Invalid promotion piece {:?}", piece),
+        };
+
+        Move::new(self.from(), self.to(), flags | (self.flags() & Move::CAPTURE))
+    }
+
+    pub fn from(&self) -> Square {
+        Square::from_u32((self.0 & FROM_MASK) as u32)
+    }
+
+    pub fn to(&self) -> Square {
+        Square::from_u32((self.0 >> TO_SHIFT & FROM_MASK) as u32)
+    }
+
+    pub fn flags(&self) -> u16 {
+        self.0 >> FLAGS_SHIFT
+    }
+
+    pub fn is_double_push(&self) -> bool {
+        self.flags() == Move::DOUBLE_PUSH
+    }
+
+    pub fn is_castling(&self) -> bool {
+        self.flags() == Move::KING_CASTLE || self.flags() == Move::QUEEN_CASTLE
+    }
+
+    pub fn is_queen_castle(&self) -> bool {
+        self.flags() == Move::QUEEN_CASTLE
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.flags() & Move::CAPTURE != 0
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        self.flags() == Move::EN_PASSANT
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        self.flags() & Move::KNIGHT_PROMOTION != 0
+    }
+
+    pub fn promotion(&self) -> Option<Piece> {
+        match self.flags() {
+            Move::KNIGHT_PROMOTION | Move::KNIGHT_PROMOTION_CAPTURE => Some(Piece::Knight),
+            Move::BISHOP_PROMOTION | Move::BISHOP_PROMOTION_CAPTURE => Some(Piece::Bishop),
+            Move::ROOK_PROMOTION | Move::ROOK_PROMOTION_CAPTURE => Some(Piece::Rook),
+            Move::QUEEN_PROMOTION | Move::QUEEN_PROMOTION_CAPTURE => Some(Piece::Queen),
+            _ => None,
         }
     }
 
     pub fn to_string(&self) -> String {
-        let res: String = format!("{}{}", self.from.to_string(), self.to.to_string());
+        let res: String = format!("{}{}", self.from().to_string(), self.to().to_string());
 
-        if self.promotion.is_some() {
-            format!("{}{}", res, self.promotion.unwrap().to_char())
-        } else {
-            res
+        match self.promotion() {
+            Some(promotion) => format!("{}{}", res, promotion.to_char()),
+            None => res,
         }
     }
 }
 
-impl Chessboard {
-    pub fn generate_legal_moves(&mut self) -> Vec<Move> {
-        let pseudo_moves = self.generate_moves();
-        let mut legal_moves = Vec::new();
-        self.is_checkmate = false;
-        self.is_stalemate = false;
-
-        for mv in pseudo_moves {
-            self.make_move(&mv);
-            if !self.is_making_check(&self.turn) {
-                legal_moves.push(mv.clone());
-            }
-            self.unmake_move(&mv);
+// Which pseudo legal moves to generate. Captures include every promotion,
+// so that captures and quiets together give all the moves. Evasions are the
+// moves that may get the side to move out of check: king moves and moves
+// capturing the checker or blocking the check.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum GenType {
+    All,
+    Captures,
+    Quiets,
+    Evasions,
+}
+
+// Each error holds the move as it was received.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum MoveParseError {
+    // UCI moves have four characters, five with a promotion.
+    InvalidLength(String),
+    InvalidSquare(String),
+    InvalidPromotion(String),
+    IllegalMove(String),
+}
+
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveParseError::InvalidLength(mv) => write!(f, "move {} is not 4 or 5 characters long", mv),
+            MoveParseError::InvalidSquare(mv) => write!(f, "invalid square in move {}", mv),
+            MoveParseError::InvalidPromotion(mv) => write!(f, "invalid promotion piece in move {}", mv),
+            MoveParseError::IllegalMove(mv) => write!(f, "illegal move {}", mv),
         }
-        legal_moves.append(&mut piece::king::generate_castling_moves(self, &self.turn));
+    }
+}
+
+impl std::error::Error for MoveParseError {}
+
+fn parse_square(square: &str) -> Option<Square> {
+    match square.as_bytes() {
+        [b'a'..=b'h', b'1'..=b'8'] => Some(Square::from_string(square)),
+        _ => None,
+    }
+}
 
-        legal_moves
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Move({}, flags {:04b})", self.to_string(), self.flags())
     }
+}
 
-    pub fn generate_moves(&self) -> Vec<Move> {
-        let moves = &mut Vec::new();
+impl Chessboard {
+    pub fn generate_moves(&self, gen: GenType) -> MoveList {
+        let mut moves = MoveList::new();
 
-        let generations: [(fn(u64, &Chessboard, &Color, &mut Vec<Move>), Piece); 6] = [
+        let generations: [(fn(u64, &Chessboard, &Color, GenType, &mut MoveList), Piece); 6] = [
             (piece::pawn::generate_pseudo_moves, Piece::Pawn),
             (piece::knight::generate_pseudo_moves, Piece::Knight),
             (piece::king::generate_pseudo_moves, Piece::King),
@@ -74,32 +193,42 @@ impl Chessboard {
                 self.get_pieces_color(piece, &self.turn),
                 self,
                 &self.turn,
-                moves);
+                gen,
+                &mut moves);
         }
 
-        moves.to_vec()
+        moves
     }
 
-    pub fn generate_move_from_string(
-        &self,
-        mv: String,
-    ) -> Move {
-        let from = Square::from_string(&mv[0..2]);
-        let to = Square::from_string(&mv[2..4]);
-
-        let mut res = self.generate_move(from, to);
-
-        if mv.len() == 5 {
-            res.promotion = match mv[4..5].chars().nth(0).unwrap() {
-                'q' => Some(Piece::Queen),
-                'r' => Some(Piece::Rook),
-                'n' => Some(Piece::Bishop),
-                'b' => Some(Piece::Knight),
-                _ => panic!("Wrong promotion type {message}", message = mv),
-            };
+    // For tests, the move must be legal.
+    #[cfg(test)]
+    pub fn generate_move_from_string(&self, mv: String) -> Move {
+        self.parse_uci_move(&mv).unwrap()
+    }
+
+    // Legal move written in UCI notation. Castling is accepted both as the
+    // king's two square move and as the king taking its own rook.
+    pub fn parse_uci_move(&self, uci: &str) -> Result<Move, MoveParseError> {
+        let error = |make: fn(String) -> MoveParseError| make(uci.to_string());
+
+        if !(4..=5).contains(&uci.chars().count()) {
+            return Err(error(MoveParseError::InvalidLength));
+        }
+
+        // Squares are only made of ASCII characters, which makes the slices
+        // below safe.
+        let (squares, promotion) = uci.split_at(uci.char_indices().nth(4).map_or(uci.len(), |(index, _)| index));
+        if !squares.is_ascii() || parse_square(&squares[0..2]).is_none() || parse_square(&squares[2..4]).is_none() {
+            return Err(error(MoveParseError::InvalidSquare));
+        }
+
+        if !matches!(promotion, "" | "n" | "b" | "r" | "q") {
+            return Err(error(MoveParseError::InvalidPromotion));
         }
 
-        res
+        self.generate_legal_moves(GenType::All).into_iter()
+            .find(|mv| self.move_to_uci(mv) == uci || mv.to_string() == uci)
+            .ok_or_else(|| error(MoveParseError::IllegalMove))
     }
 
     pub fn generate_move(
@@ -107,54 +236,27 @@ impl Chessboard {
         from: Square,
         to: Square,
     ) -> Move {
-        let mut res = Move::new(from, to);
-
-        // Handle capture
-        if let Some(capture) = self.get_piece(&res.to) {
-            res.capture = Some(capture);
-        } else if let Some(piece) = self.get_piece(&res.from) {
-            // Handle en passant
-            if piece == Piece::Pawn {
-                let en_passant = self.en_passant;
-                if en_passant != None && res.to == en_passant.unwrap() {
-                    res.capture = Some(Piece::Pawn);
-                }
-            }
-        }
-
-        // Handle promotion
-        //if let Some(piece) = self.get_piece(&res.from) {
-        //    if piece == Piece::Pawn {
-        //        self.generate_promotion_moves(&mut moves, res);
-        //    }
-        //    else {
-        //        moves.push(res);
-        //    }
-        //}
-        //else {
-        //    moves.push(res);
-        //}
+        let piece = self.get_piece(&from);
+
+        let flags = if self.get_color(&to).is_some() && self.get_color(&to) != self.get_color(&from) {
+            Move::CAPTURE
+        } else if piece == Some(Piece::Pawn) && self.en_passant == Some(to) {
+            Move::EN_PASSANT
+        } else if piece == Some(Piece::Pawn) && (from.to_u32() as i32 - to.to_u32() as i32).abs() == 2 * NORT {
+            Move::DOUBLE_PUSH
+        } else {
+            Move::QUIET
+        };
 
-        res
+        Move::new(from, to, flags)
     }
 
-    fn generate_promotion_moves(&self, moves: &mut Vec<Move>, mv: Move) {
-        let mut mv = mv;
-
-        let rank_promotion = match self.turn {
-            Color::White => Rank::Eight,
-            Color::Black => Rank::One,
-        };
-
-        if mv.to.rank == rank_promotion {
-            for promotion in vec![
-                Piece::Knight,
-                Piece::Bishop,
-                Piece::Rook,
-                Piece::Queen
-            ] {
-                mv.promotion = Some(promotion);
-                moves.push(mv);
+    // Pawns never move to their own back rank, so reaching either one is
+    // a promotion whoever moves.
+    fn generate_promotion_moves(&self, moves: &mut MoveList, mv: Move) {
+        if matches!(mv.to().rank, Rank::One | Rank::Eight) {
+            for promotion in PROMOTION_PIECES.iter() {
+                moves.push(mv.with_promotion(promotion));
             }
         }
         else {
@@ -162,72 +264,72 @@ impl Chessboard {
         }
     }
 
+    // UCI notation of a move: in standard chess castling is sent as the
+    // king's two square move.
+    pub fn move_to_uci(&self, mv: &Move) -> String {
+        if mv.is_castling() && !self.chess960 {
+            let (king_dest, _) = piece::king::castling_destinations(mv.from().rank, mv.is_queen_castle());
+            return format!("{}{}", mv.from().to_string(), king_dest.to_string());
+        }
+
+        mv.to_string()
+    }
+
+    // Square of the piece taken by a move, which differs from the
+    // destination for en passant.
+    fn captured_square(mv: &Move) -> Square {
+        match mv.is_en_passant() {
+            true => Square::new(mv.to().file, mv.from().rank),
+            false => mv.to(),
+        }
+    }
+
+    // Piece a move would take, looked up on the board before it is made.
+    pub fn captured_piece(&self, mv: &Move) -> Option<Piece> {
+        match mv.is_capture() {
+            true => self.get_piece(&Chessboard::captured_square(mv)),
+            false => None,
+        }
+    }
+
     fn make_castling(&mut self, mv: &Move) {
-        let square_rook_src: Square;
-        let square_rook_dst: Square;
+        let color = self.turn;
+        let (king_dest, rook_dest) = piece::king::castling_destinations(mv.from().rank, mv.is_queen_castle());
 
-        match mv.to.file {
-            File::C => {
-                square_rook_src = Square::new(File::A, mv.to.rank);
-                square_rook_dst = Square::new(File::D, mv.to.rank);
-            },
-            File::G => {
-                square_rook_src = Square::new(File::H, mv.to.rank);
-                square_rook_dst = Square::new(File::F, mv.to.rank);
-            },
-            _ => panic!("Wrong castling move"),
-        };
+        self.remove_piece(&Piece::King, &color, &mv.from());
+        self.remove_piece(&Piece::Rook, &color, &mv.to());
+        self.put_piece(&Piece::King, &color, &king_dest);
+        self.put_piece(&Piece::Rook, &color, &rook_dest);
+    }
 
-        let square_src_dst = square_rook_src.to_bitboard() | square_rook_dst.to_bitboard();
+    fn unmake_castling(&mut self, mv: &Move) {
+        let color = self.turn;
+        let (king_dest, rook_dest) = piece::king::castling_destinations(mv.from().rank, mv.is_queen_castle());
 
-        self.piece_board[Piece::Rook.to_usize()] ^= square_src_dst;
-        self.color_board[self.turn.to_usize()] ^= square_src_dst;
-        self.empty_board ^= square_src_dst;
+        self.remove_piece(&Piece::King, &color, &king_dest);
+        self.remove_piece(&Piece::Rook, &color, &rook_dest);
+        self.put_piece(&Piece::King, &color, &mv.from());
+        self.put_piece(&Piece::Rook, &color, &mv.to());
     }
 
-    fn update_castling_rights(&mut self) {
-        let square_e1 = self.get_piece(&Square::from_string("e1"));
-        if self.white_castle.0 {
-            if square_e1 == None || square_e1.unwrap() != Piece::King {
-                self.white_castle.0 = false;
-                self.white_castle.1 = false;
-            }
-            let color_h1 = self.get_color(&Square::from_string("h1"));
-            if color_h1 != Some(Color::White) {
-                self.white_castle.0 = false;
-            }
-        }
-        if self.white_castle.1 {
-            if square_e1 == None || square_e1.unwrap() != Piece::King {
-                self.white_castle.0 = false;
-                self.white_castle.1 = false;
-            }
-            let color_a1 = self.get_color(&Square::from_string("a1"));
-            if color_a1 != Some(Color::White) {
-                self.white_castle.1 = false;
-            }
+    // A right is lost when the king moves, or when anything leaves or lands
+    // on the square of the rook it refers to.
+    fn update_castling_rights(&mut self, mv: &Move, piece: &Piece) {
+        if *piece == Piece::King {
+            *self.castling_rights_mut(&self.turn.clone()) = (None, None);
         }
 
-        let square_e8 = self.get_piece(&Square::from_string("e8"));
-        if self.black_castle.0 {
-            if square_e8 == None || square_e8.unwrap() != Piece::King {
-                self.black_castle.0 = false;
-                self.black_castle.1 = false;
-            }
-            let color_h8 = self.get_color(&Square::from_string("h8"));
-            if color_h8 != Some(Color::Black) {
-                self.black_castle.0 = false;
-            }
-        }
-        if self.black_castle.1 {
-            let square_e8 = self.get_piece(&Square::from_string("e8"));
-            if square_e8 == None || square_e8.unwrap() != Piece::King {
-                self.black_castle.0 = false;
-                self.black_castle.1 = false;
-            }
-            let color_a8 = self.get_color(&Square::from_string("a8"));
-            if color_a8 != Some(Color::Black) {
-                self.black_castle.1 = false;
+        for color in [Color::White, Color::Black] {
+            let rank = piece::king::back_rank(&color);
+            let rights = self.castling_rights_mut(&color);
+
+            for right in [&mut rights.0, &mut rights.1] {
+                if let Some(file) = *right {
+                    let square = Square::new(file, rank);
+                    if mv.from() == square || mv.to() == square {
+                        *right = None;
+                    }
+                }
             }
         }
     }
@@ -235,124 +337,123 @@ impl Chessboard {
     pub fn make_move(&mut self, mv: &Move) {
         //self.pretty_print();
         //println!("Move: {}", mv.to_string());
-        self.push();
+        self.push(self.captured_piece(mv));
+        self.hash ^= self.state_key();
         if self.perft_depth > 0 {
             self.perft_depth -= 1;
         }
-        let mut piece = self.get_piece(&mv.from).unwrap();
+        let from = mv.from();
+        let to = mv.to();
+        let piece = self.get_piece(&from).unwrap();
+        let color = self.turn;
         let opposite = self.get_opposite_color(&self.turn);
-        let mut next_ep = None;
 
-        self.piece_board[piece.to_usize()] ^= mv.from.to_bitboard();
-        self.color_board[self.turn.to_usize()] ^= mv.from.to_bitboard();
-        self.empty_board ^= mv.from.to_bitboard();
+        if piece == Piece::Pawn || mv.is_capture() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        }
 
-        if piece == Piece::Pawn {
-            let diff: i32 = mv.from.to_u32() as i32 - mv.to.to_u32() as i32; 
+        let captured = self.history.last().unwrap().captured;
 
-            if diff.abs() == (2 * NORT) {
-                next_ep = Some(Square::from_u32((mv.from.to_u32() as i32 - diff / 2) as u32));
-            } else if mv.promotion != None {
-                piece = mv.promotion.unwrap();
-            }
-        } else if piece == Piece::King {
-            if ((mv.from.to_u32() as i32 - mv.to.to_u32() as i32) as i32).abs() == 2 {
-                self.make_castling(mv);
-            }
-        }
+        let mut next_ep = None;
 
-        let captured = mv.capture;
-
-        // Handle capture
-        if captured != None {
-            let captured = captured.unwrap();
-
-            // En passant
-            if piece == Piece::Pawn
-                && captured == Piece::Pawn
-                && self.en_passant != None
-                && mv.to == self.en_passant.unwrap()
-            {
-                let real_pos = mv.to.to_u32() as i32 + match self.turn {
-                    Color::White => SOUT,
-                    Color::Black => NORT,
-                };
-                let real_square = Square::from_u32(real_pos as u32);
-                self.piece_board[captured.to_usize()] ^= real_square.to_bitboard();
-                self.color_board[opposite.to_usize()] ^= real_square.to_bitboard();
-                self.empty_board ^= real_square.to_bitboard() | mv.to.to_bitboard();
-            } else {
-                self.piece_board[captured.to_usize()] ^= mv.to.to_bitboard();
-                self.color_board[opposite.to_usize()] ^= mv.to.to_bitboard();
-            }
-        } else {
-            self.empty_board ^= mv.to.to_bitboard();
-        }
+        match mv.flags() {
+            Move::KING_CASTLE | Move::QUEEN_CASTLE => self.make_castling(mv),
+            _ => {
+                if let Some(captured) = captured {
+                    self.remove_piece(&captured, &opposite, &Chessboard::captured_square(mv));
+                }
 
-        self.piece_board[piece.to_usize()] |= mv.to.to_bitboard();
-        self.color_board[self.turn.to_usize()] |= mv.to.to_bitboard();
+                self.remove_piece(&piece, &color, &from);
+                self.put_piece(&mv.promotion().unwrap_or(piece), &color, &to);
 
-        self.update_castling_rights();
+                if mv.is_double_push() {
+                    next_ep = Some(Square::from_u32((from.to_u32() + to.to_u32()) / 2));
+                }
+            },
+        }
+
+        self.update_castling_rights(mv, &piece);
 
         self.turn = opposite;
-        //self.halfmove_clock += 1;
         self.en_passant = next_ep;
+        self.hash ^= self.state_key() ^ zobrist::side_key();
+        self.checkers = self.compute_checkers();
 
         if self.turn == Color::White {
             self.fullmove_number += 1;
         }
+
+        debug_assert_eq!(self.hash, self.compute_hash(), "Hash mismatch after {}", mv.to_string());
+        debug_assert!(self.is_mailbox_consistent(), "Mailbox mismatch after {}", mv.to_string());
     }
 
     pub fn unmake_move(&mut self, mv: &Move) {
         self.perft_depth += 1;
         self.turn = self.get_opposite_color(&self.turn);
-        let mut piece = self.get_piece(&mv.to).unwrap();
+        let color = self.turn;
+        let captured = self.history.last().unwrap().captured;
 
-        self.piece_board[piece.to_usize()] ^= mv.to.to_bitboard();
-        self.color_board[self.turn.to_usize()] ^= mv.to.to_bitboard();
-        self.empty_board ^= mv.to.to_bitboard();
+        if mv.is_castling() {
+            self.unmake_castling(mv);
+        } else {
+            let piece = self.get_piece(&mv.to()).unwrap();
+            self.remove_piece(&piece, &color, &mv.to());
 
-        if mv.promotion != None {
-            piece = Piece::Pawn;
-        }
+            let piece = match mv.is_promotion() {
+                true => Piece::Pawn,
+                false => piece,
+            };
+            self.put_piece(&piece, &color, &mv.from());
 
-        self.piece_board[piece.to_usize()] |= mv.from.to_bitboard();
-        self.color_board[self.turn.to_usize()] |= mv.from.to_bitboard();
-        self.empty_board ^= mv.from.to_bitboard();
+            if let Some(captured) = captured {
+                self.put_piece(&captured, &color.opposite(), &Chessboard::captured_square(mv));
+            }
+        }
 
+        // Restores the hash as well, so the piece updates above need not
+        // keep it right.
         self.pop();
 
-        if piece == Piece::King {
-            if ((mv.from.to_u32() as i32 - mv.to.to_u32() as i32) as i32).abs() == 2 {
-                self.make_castling(mv);
-            }
+        if self.turn == Color::Black {
+            self.fullmove_number -= 1;
         }
 
-        // Handle capture
-        if let Some(capture) = mv.capture {
-            if piece == Piece::Pawn
-                && capture == Piece::Pawn
-                && self.en_passant != None
-                && mv.to == self.en_passant.unwrap()
-            {
-                let real_pos = mv.to.to_u32() as i32 + match self.turn {
-                    Color::White => SOUT,
-                    Color::Black => NORT,
-                };
-                let real_square = Square::from_u32(real_pos as u32);
-                self.piece_board[capture.to_usize()] |= real_square.to_bitboard();
-                self.color_board[self.turn.opposite().to_usize()] |= real_square.to_bitboard();
-                self.empty_board ^= real_square.to_bitboard();
-            } else {
-                self.piece_board[capture.to_usize()] |= mv.to.to_bitboard();
-                self.color_board[self.turn.opposite().to_usize()] |= mv.to.to_bitboard();
-                self.empty_board ^= mv.to.to_bitboard();
-            }
+        debug_assert_eq!(self.hash, self.compute_hash(), "Hash mismatch after undoing {}", mv.to_string());
+        debug_assert!(self.is_mailbox_consistent(), "Mailbox mismatch after undoing {}", mv.to_string());
+    }
+
+    // Pass the turn, for null move pruning. The side to move must not be in
+    // check, as the resulting position would not be legal.
+    pub fn make_null_move(&mut self) {
+        debug_assert!(!self.is_in_check(&self.turn), "Null move while in check");
+
+        self.push(None);
+        self.hash ^= self.state_key();
+
+        self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        self.en_passant = None;
+        self.turn = self.get_opposite_color(&self.turn);
+        self.hash ^= self.state_key() ^ zobrist::side_key();
+        self.checkers = self.compute_checkers();
+
+        if self.turn == Color::White {
+            self.fullmove_number += 1;
         }
 
+        debug_assert_eq!(self.hash, self.compute_hash(), "Hash mismatch after null move");
+    }
+
+    pub fn unmake_null_move(&mut self) {
+        self.turn = self.get_opposite_color(&self.turn);
+        self.pop();
+
         if self.turn == Color::Black {
             self.fullmove_number -= 1;
         }
+
+        debug_assert_eq!(self.hash, self.compute_hash(), "Hash mismatch after undoing null move");
     }
 }
 
@@ -374,24 +475,115 @@ mod tests {
         assert_eq!(actual.white_castle, expected.white_castle, "Test white castle");
         assert_eq!(actual.black_castle, expected.black_castle, "Test black castle");
         assert_eq!(actual.en_passant, expected.en_passant, "Test en passant");
-        // assert_eq!(actual.halfmove_clock, expected.halfmove_clock, "Test halfmove");
+        assert_eq!(actual.halfmove_clock, expected.halfmove_clock, "Test halfmove");
         assert_eq!(actual.fullmove_number, expected.fullmove_number, "Test fullmove");
         assert_eq!(actual.perft_depth, expected.perft_depth, "Test depth");
+        assert_eq!(actual.hash(), expected.hash(), "Test hash");
+        assert_eq!(actual.checkers(), expected.checkers(), "Test checkers");
     }
 
     #[test]
     fn test_generate_move_from_string() {
         let chessboard = Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
         let mv = chessboard.generate_move_from_string("e2e4".to_string());
-        let expected: Move = Move {
-            from: Square::from_string("e2"),
-            to: Square::from_string("e4"),
-            capture: None,
-            promotion: None,
-        };
+        let expected = Move::new(Square::from_string("e2"), Square::from_string("e4"), Move::DOUBLE_PUSH);
         assert_eq!(mv, expected);
     }
 
+    #[test]
+    fn test_move_encoding() {
+        let from = Square::from_string("b7");
+        let to = Square::from_string("a8");
+        let mv = Move::new(from, to, Move::CAPTURE).with_promotion(&Piece::Queen);
+        assert_eq!(mv.from(), from);
+        assert_eq!(mv.to(), to);
+        assert_eq!(mv.flags(), Move::QUEEN_PROMOTION_CAPTURE);
+        assert!(mv.is_capture() && mv.is_promotion() && !mv.is_en_passant());
+        assert_eq!(mv.promotion(), Some(Piece::Queen));
+        assert_eq!(mv.to_string(), "b7a8q");
+
+        let mv = Move::castle(Square::from_string("e1"), Square::from_string("a1"));
+        assert!(mv.is_castling() && mv.is_queen_castle() && !mv.is_capture());
+        assert_eq!(mv.promotion(), None);
+    }
+
+    #[test]
+    fn test_move_flags_from_string() {
+        let chessboard = Chessboard::new("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1".to_string());
+        let flags = |mv: &str| chessboard.generate_move_from_string(mv.to_string()).flags();
+
+        assert_eq!(flags("a1a2"), Move::QUIET);
+        assert_eq!(flags("a1a8"), Move::CAPTURE);
+        assert_eq!(flags("e5d6"), Move::EN_PASSANT);
+        assert_eq!(flags("e1g1"), Move::KING_CASTLE);
+        assert_eq!(flags("e1h1"), Move::KING_CASTLE);
+        assert_eq!(flags("e1c1"), Move::QUEEN_CASTLE);
+        assert_eq!(flags("b7b8q"), Move::QUEEN_PROMOTION);
+        assert_eq!(flags("b7a8r"), Move::ROOK_PROMOTION_CAPTURE);
+        assert_eq!(flags("b7b8n"), Move::KNIGHT_PROMOTION);
+        assert_eq!(flags("b7b8b"), Move::BISHOP_PROMOTION);
+
+        let chessboard = Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
+        assert_eq!(chessboard.generate_move_from_string("e2e4".to_string()).flags(), Move::DOUBLE_PUSH);
+
+        // Every legal move survives the trip through its UCI string.
+        for mv in chessboard.generate_legal_moves(GenType::All) {
+            let uci = chessboard.move_to_uci(&mv);
+            assert_eq!(chessboard.generate_move_from_string(uci), mv);
+        }
+
+        let chessboard = Chessboard::new("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string());
+        let castle = chessboard.generate_move_from_string("e1c1".to_string());
+        assert_eq!(castle.to_string(), "e1a1");
+        assert_eq!(chessboard.move_to_uci(&castle), "e1c1");
+    }
+
+    #[test]
+    fn test_parse_uci_move() {
+        let chessboard = Chessboard::new("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1".to_string());
+        let parse = |uci: &str| chessboard.parse_uci_move(uci);
+
+        assert_eq!(parse("e5d6").unwrap().flags(), Move::EN_PASSANT);
+        assert_eq!(parse("a1a8").unwrap().flags(), Move::CAPTURE);
+
+        for (piece, quiet, capture) in [
+            ('n', Move::KNIGHT_PROMOTION, Move::KNIGHT_PROMOTION_CAPTURE),
+            ('b', Move::BISHOP_PROMOTION, Move::BISHOP_PROMOTION_CAPTURE),
+            ('r', Move::ROOK_PROMOTION, Move::ROOK_PROMOTION_CAPTURE),
+            ('q', Move::QUEEN_PROMOTION, Move::QUEEN_PROMOTION_CAPTURE),
+        ] {
+            assert_eq!(parse(&format!("b7b8{}", piece)).unwrap().flags(), quiet);
+            assert_eq!(parse(&format!("b7a8{}", piece)).unwrap().flags(), capture);
+        }
+
+        for (uci, rook) in [("e1g1", "h1"), ("e1h1", "h1"), ("e1c1", "a1"), ("e1a1", "a1")] {
+            assert_eq!(parse(uci), Ok(Move::castle(Square::from_string("e1"), Square::from_string(rook))), "{}", uci);
+        }
+
+        assert_eq!(parse("b7b8"), Err(MoveParseError::IllegalMove(String::from("b7b8"))));
+        assert_eq!(parse("b7b8k"), Err(MoveParseError::InvalidPromotion(String::from("b7b8k"))));
+        assert_eq!(parse("a2a4q"), Err(MoveParseError::IllegalMove(String::from("a2a4q"))));
+        assert_eq!(parse("e1e3"), Err(MoveParseError::IllegalMove(String::from("e1e3"))));
+        assert_eq!(parse("e1"), Err(MoveParseError::InvalidLength(String::from("e1"))));
+        assert_eq!(parse("é1e2"), Err(MoveParseError::InvalidSquare(String::from("é1e2"))));
+        assert_eq!(parse("e1e2é"), Err(MoveParseError::InvalidPromotion(String::from("e1e2é"))));
+        assert_eq!(parse("e1éé"), Err(MoveParseError::InvalidSquare(String::from("e1éé"))));
+        assert_eq!(parse("i1e2"), Err(MoveParseError::InvalidSquare(String::from("i1e2"))));
+        assert_eq!(parse("e0e2"), Err(MoveParseError::InvalidSquare(String::from("e0e2"))));
+
+        // In Chess960 the king only castles by taking its rook.
+        let chessboard = Chessboard::new("4k3/8/8/8/8/8/8/1R2K1R1 w GB - 0 1".to_string());
+        assert_eq!(chessboard.parse_uci_move("e1g1"), Ok(Move::castle(Square::from_string("e1"), Square::from_string("g1"))));
+        assert_eq!(chessboard.parse_uci_move("e1b1"), Ok(Move::castle(Square::from_string("e1"), Square::from_string("b1"))));
+        assert_eq!(chessboard.parse_uci_move("e1c1"), Err(MoveParseError::IllegalMove(String::from("e1c1"))));
+
+        // Every legal move survives the trip through its UCI string.
+        let chessboard = Chessboard::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".to_string());
+        for mv in chessboard.generate_legal_moves(GenType::All) {
+            assert_eq!(chessboard.parse_uci_move(&chessboard.move_to_uci(&mv)), Ok(mv));
+        }
+    }
+
     fn test_make_move(fen: &str, mv: &str, expected: &str) {
         let mut chessboard = Chessboard::new(fen.to_string());
         let mv = chessboard.generate_move_from_string(mv.to_string());
@@ -453,4 +645,109 @@ mod tests {
         test_unmake_move("8/P7/8/8/8/8/8/8 w - - 0 1 1", "a7a8q");
         test_unmake_move("8/8/8/8/8/8/p7/8 b - - 0 1 1", "a2a1q");
     }
+
+    #[test]
+    fn test_null_move() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 3 12",
+        ] {
+            let mut chessboard = Chessboard::new(fen.to_string());
+            let expected = chessboard.clone();
+
+            chessboard.make_null_move();
+            assert_ne!(chessboard.turn, expected.turn);
+            assert_eq!(chessboard.en_passant, None);
+            assert_eq!(chessboard.hash(), chessboard.compute_hash());
+
+            chessboard.unmake_null_move();
+            compare_chessboard(chessboard, expected);
+        }
+
+        // Two null moves give back the same position, without en passant.
+        let mut chessboard = Chessboard::new("4k3/8/8/8/8/8/8/4K3 w - - 0 1".to_string());
+        let start = chessboard.hash();
+        chessboard.make_null_move();
+        chessboard.make_null_move();
+        assert_eq!(chessboard.hash(), start);
+        assert_eq!(chessboard.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 2 2");
+    }
+
+    #[test]
+    #[should_panic(expected = "Null move while in check")]
+    #[cfg(debug_assertions)]
+    fn test_null_move_in_check() {
+        let mut chessboard = Chessboard::new("4k3/8/8/8/8/8/8/4K2r w - - 0 1".to_string());
+        chessboard.make_null_move();
+    }
+
+    fn sorted(mut moves: Vec<Move>) -> Vec<Move> {
+        moves.sort();
+        moves
+    }
+
+    const GEN_TYPE_FENS: [&str; 5] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+    ];
+
+    #[test]
+    fn test_generate_captures_and_quiets() {
+        let generators: [fn(&Chessboard, GenType) -> MoveList; 2] = [
+            Chessboard::generate_moves,
+            Chessboard::generate_legal_moves,
+        ];
+
+        for fen in GEN_TYPE_FENS {
+            let chessboard = Chessboard::new(fen.to_string());
+
+            for generate in generators {
+                let captures = generate(&chessboard, GenType::Captures);
+                let quiets = generate(&chessboard, GenType::Quiets);
+
+                assert!(captures.iter().all(|mv| mv.is_capture() || mv.is_promotion()), "{}", fen);
+                assert!(quiets.iter().all(|mv| !mv.is_capture() && !mv.is_promotion()), "{}", fen);
+
+                let staged = captures.into_iter().chain(quiets).collect();
+                assert_eq!(sorted(staged), sorted(generate(&chessboard, GenType::All).to_vec()), "{}", fen);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_evasions() {
+        for fen in GEN_TYPE_FENS {
+            let mut chessboard = Chessboard::new(fen.to_string());
+
+            for mv in chessboard.generate_legal_moves(GenType::All) {
+                chessboard.make_move(&mv);
+
+                if chessboard.checkers() != 0 {
+                    let evasions = chessboard.generate_moves(GenType::Evasions);
+                    let all = chessboard.generate_moves(GenType::All);
+
+                    assert!(evasions.iter().all(|evasion| all.contains(evasion)), "{}", chessboard.to_fen());
+                    assert!(chessboard.generate_legal_moves(GenType::All).iter().all(|legal| evasions.contains(legal)), "{}", chessboard.to_fen());
+
+                    // In check, every legal move is an evasion.
+                    assert_eq!(
+                        sorted(chessboard.generate_legal_moves(GenType::Evasions).to_vec()),
+                        sorted(chessboard.generate_legal_moves(GenType::All).to_vec()),
+                        "{}", chessboard.to_fen());
+                }
+
+                chessboard.unmake_move(&mv);
+            }
+        }
+
+        // Double check, only the king moves.
+        let chessboard = Chessboard::new("4k3/8/8/8/1b6/8/4r3/R3K2R w KQ - 0 1".to_string());
+        let evasions = chessboard.generate_moves(GenType::Evasions);
+        assert!(!evasions.is_empty());
+        assert!(evasions.iter().all(|mv| mv.from() == Square::from_string("e1")));
+    }
 }
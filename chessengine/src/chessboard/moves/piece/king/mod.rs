@@ -1,113 +1,120 @@
 use crate::chessboard::Chessboard;
 
+use bitboard::attacks::KING_ATTACKS;
+
 use utils::color::Color;
-use utils::direction;
 use utils::file::File;
+use utils::piece::Piece;
 use utils::rank::Rank;
 
 use super::*;
 
-const KING_MOVES: [(i32, fn(u64) -> u64); 8] = [
-    (direction::NORT, bitboard::north_one),
-    (direction::NOEA, bitboard::no_east_one),
-    (direction::EAST, bitboard::east_one),
-    (direction::SOEA, bitboard::so_east_one),
-    (direction::SOUT, bitboard::south_one),
-    (direction::SOWE, bitboard::so_west_one),
-    (direction::WEST, bitboard::west_one),
-    (direction::NOWE, bitboard::no_west_one),
-];
-
 pub fn generate_pseudo_moves(
     king: u64,
     chessboard: &Chessboard,
     color: &Color,
-    moves: &mut Vec<Move>,
+    gen: GenType,
+    moves: &mut MoveList,
 ) {
-    let allies = chessboard.get_colors(&color);
+    // The king evades by moving anywhere.
+    let targets = match gen {
+        GenType::Evasions => !chessboard.get_colors(color),
+        _ => gen_targets(chessboard, color, gen),
+    };
 
-    if king == 0 {
-        return;
-    }
+    generate_leaper_moves(king, chessboard, targets, &KING_ATTACKS, moves);
+}
 
-    for (direction, f) in KING_MOVES.iter() {
-        let targets = f(king) & !allies;
+pub fn back_rank(color: &Color) -> Rank {
+    match color {
+        Color::White => Rank::One,
+        Color::Black => Rank::Eight,
+    }
+}
 
-        convert_bb_to_moves(chessboard, targets, -*direction, moves);
+// Squares the king and the rook land on after castling, which are the same
+// in Chess960 and in standard chess.
+pub fn castling_destinations(rank: Rank, queen_side: bool) -> (Square, Square) {
+    match queen_side {
+        true => (Square::new(File::C, rank), Square::new(File::D, rank)),
+        false => (Square::new(File::G, rank), Square::new(File::F, rank)),
     }
 }
 
-fn generate_castle_move(chessboard: &Chessboard, square: &Square, color: &Color, queen_side: bool) -> Vec<Move> {
-    let castle_ability = match color {
-        Color::White => match queen_side {
-            true => chessboard.white_castle.1,
-            false => chessboard.white_castle.0,
-        },
-        Color::Black => match queen_side {
-            true => chessboard.black_castle.1,
-            false => chessboard.black_castle.0,
-        },
-    };
+// Squares from `a` to `b` on the same rank, both included.
+fn rank_span(a: &Square, b: &Square) -> u64 {
+    let low = a.file.min(b.file) as u32;
+    let high = a.file.max(b.file) as u32;
+    let files = (0xff >> (7 - high)) & (0xff << low);
+
+    files << (a.rank as u32 * 8)
+}
 
-    if !castle_ability {
-        return Vec::new()
+fn generate_castle_move(chessboard: &Chessboard, color: &Color, queen_side: bool) -> Option<Move> {
+    let rights = chessboard.castling_rights(color);
+    let rook_file = match queen_side {
+        true => rights.1,
+        false => rights.0,
+    }?;
+
+    let rank = back_rank(color);
+    let king = chessboard.get_pieces_color(&Piece::King, color);
+    let king_square = Square::from_u32(king.trailing_zeros() % 64);
+    let rook_square = Square::new(rook_file, rank);
+
+    // Rights loaded from a FEN are not trusted to match the board.
+    if king.count_ones() != 1
+        || king_square.rank != rank
+        || chessboard.get_pieces_color(&Piece::Rook, color) & rook_square.to_bitboard() == 0
+        || (rook_file < king_square.file) != queen_side
+    {
+        return None;
     }
 
-    let direction = match queen_side {
-        true => direction::WEST,
-        false => direction::EAST,
-    };
+    let (king_dest, rook_dest) = castling_destinations(rank, queen_side);
 
-    let dest_square = Square::from_u32((square.to_u32() as i32 + 2 * direction) as u32);
-    let dest_square_to_check = match queen_side {
-        true => Square::from_u32((dest_square.to_u32() as i32 + direction) as u32),
-        false => dest_square,
-    };
+    // Check if the squares the king and the rook go through are empty,
+    // ignoring the two castling pieces themselves
+    let occupied = !chessboard.empty_board & !(king | rook_square.to_bitboard());
+    let king_path = rank_span(&king_square, &king_dest);
+    if (king_path | rank_span(&rook_square, &rook_dest)) & occupied != 0 {
+        return None;
+    }
 
-    let mut distance = 1;
-    // Check if squares between king and rook are empty
-    loop {
-        let square_to_check = &Square::from_u32((square.to_u32() as i32 + direction * distance) as u32);
-        if chessboard.get_piece(square_to_check) != None {
-            return Vec::new()
-        } else if square_to_check == &dest_square_to_check {
-            break
+    // Check if the squares the king goes through are not attacked
+    let mut squares = king_path;
+    while squares != 0 {
+        let square = squares.trailing_zeros();
+        if chessboard.is_attacked_square(1 << square, color) {
+            return None;
         }
-        distance += 1;
+        squares ^= 1 << square;
     }
 
-    // Check if the squares between the king and the rook are not attacked
-    for i in 0..3 {
-        let square = Square::from_u32((square.to_u32() as i32 + i * direction) as u32);
-        if chessboard.is_attacked_square(square.to_bitboard(), color) {
-            return Vec::new()
-        }
+    // In Chess960 the rook may have been shielding the king's destination
+    // from a slider on the back rank.
+    let occupancy = (!chessboard.empty_board ^ rook_square.to_bitboard()) | rook_dest.to_bitboard();
+    if chessboard.attackers_to(&king_dest, occupancy) & chessboard.get_colors(&color.opposite()) != 0 {
+        return None;
     }
 
-    return vec![Move::new(*square, dest_square)]
+    Some(Move::castle(king_square, rook_square))
 }
 
 pub fn generate_castling_moves(
     chessboard: &Chessboard,
     color: &Color,
-) -> Vec<Move> {
-    let mut moves = Vec::new();
-    let file_king = File::E;
-    let rank_king = match color {
-        Color::White => Rank::One,
-        Color::Black => Rank::Eight,
-    };
-    let square_king = Square::new(file_king, rank_king);
+) -> MoveList {
+    let mut moves = MoveList::new();
 
-    moves.append(&mut generate_castle_move(chessboard, &square_king, color, false));
-    moves.append(&mut generate_castle_move(chessboard, &square_king, color, true));
+    moves.extend(generate_castle_move(chessboard, color, false));
+    moves.extend(generate_castle_move(chessboard, color, true));
 
     moves
 }
 
 #[cfg(test)]
 mod tests {
-    use utils::piece::Piece;
     use super::*;
 
     #[test]
@@ -115,25 +122,25 @@ mod tests {
         let chessboard = Chessboard::new("8/8/8/8/8/8/8/8 w - - 0 1".to_string());
         let color = Color::White;
         let king = chessboard.get_pieces_color(&Piece::King, &color);
-        let moves = &mut Vec::new();
-        generate_pseudo_moves(king, &chessboard, &color, moves);
+        let moves = &mut MoveList::new();
+        generate_pseudo_moves(king, &chessboard, &color, GenType::All, moves);
         assert_eq!(moves.len(), 0);
 
         let chessboard = Chessboard::new("8/8/8/3K4/8/8/8/8 w - - 0 1".to_string());
         let color = Color::White;
         let king = chessboard.get_pieces_color(&Piece::King, &color);
-        let moves = &mut Vec::new();
-        generate_pseudo_moves(king, &chessboard, &color, moves);
+        let moves = &mut MoveList::new();
+        generate_pseudo_moves(king, &chessboard, &color, GenType::All, moves);
         assert_eq!(moves.len(), 8);
 
         let chessboard = Chessboard::new("8/8/8/8/8/8/8/KP6 w - - 0 1".to_string());
         let color = Color::White;
         let king = chessboard.get_pieces_color(&Piece::King, &color);
-        let moves = &mut Vec::new();
-        generate_pseudo_moves(king, &chessboard, &color, moves);
+        let moves = &mut MoveList::new();
+        generate_pseudo_moves(king, &chessboard, &color, GenType::All, moves);
         assert_eq!(moves.len(), 2);
-        assert!(moves.contains(&Move::new(Square::from_u32(0), Square::from_u32(8))));
-        assert!(moves.contains(&Move::new(Square::from_u32(0), Square::from_u32(9))));
+        assert!(moves.contains(&Move::new(Square::from_u32(0), Square::from_u32(8), Move::QUIET)));
+        assert!(moves.contains(&Move::new(Square::from_u32(0), Square::from_u32(9), Move::QUIET)));
     }
 
     fn test_generate_castling_moves(fen: &str, color: &Color, expected_moves: usize) {
@@ -154,11 +161,34 @@ mod tests {
 
         test_generate_castling_moves("8/8/8/8/8/8/8/4K2R w K - 0 1", &Color::White, 1);
         test_generate_castling_moves("8/8/8/8/8/8/8/R3K3 w Q - 0 1", &Color::White, 1);
-        test_generate_castling_moves("8/8/8/8/8/8/8/4K2R w KQ - 0 1", &Color::White, 2);
+        test_generate_castling_moves("8/8/8/8/8/8/8/R3K2R w KQ - 0 1", &Color::White, 2);
         test_generate_castling_moves("4k2r/8/8/8/8/8/8/8 b k - 0 1", &Color::Black, 1);
         test_generate_castling_moves("r3k3/8/8/8/8/8/8/8 b q - 0 1", &Color::Black, 1);
-        test_generate_castling_moves("4k2r/8/8/8/8/8/8/8 b kq - 0 1", &Color::Black, 2);
+        test_generate_castling_moves("r3k2r/8/8/8/8/8/8/8 b kq - 0 1", &Color::Black, 2);
 
         test_generate_castling_moves("8/8/8/8/4b3/8/8/R3K3 w Q - 0 1", &Color::White, 1);
     }
+
+    #[test]
+    fn test_castling_moves_chess960() {
+        // King on b1, rooks on a1 and g1.
+        test_generate_castling_moves("8/8/8/8/8/8/8/RK4R1 w AG - 0 1", &Color::White, 2);
+        // The king stays on g1, only the rook moves.
+        test_generate_castling_moves("8/8/8/8/8/8/8/5RKR w H - 0 1", &Color::White, 0);
+        test_generate_castling_moves("8/8/8/8/8/8/8/4R1KR w H - 0 1", &Color::White, 1);
+        // The rook on f1 blocks the king going to c1.
+        test_generate_castling_moves("8/8/8/8/8/8/8/1R3RK1 w B - 0 1", &Color::White, 0);
+        // d1 must be free for the rook.
+        test_generate_castling_moves("8/8/8/8/8/8/8/RKBN4 w A - 0 1", &Color::White, 0);
+        test_generate_castling_moves("8/8/8/8/8/8/8/RK6 w A - 0 1", &Color::White, 1);
+        // The king path c1-g1 is attacked on e1.
+        test_generate_castling_moves("4r3/8/8/8/8/8/8/1RK4R w HB - 0 1", &Color::White, 1);
+        // The rook on b1 hides c1 from the rook on a1 until it moves.
+        test_generate_castling_moves("8/8/8/8/8/8/8/rRK5 w B - 0 1", &Color::White, 0);
+
+        let chessboard = Chessboard::new("8/8/8/8/8/8/8/RK4R1 w AG - 0 1".to_string());
+        let moves = generate_castling_moves(&chessboard, &Color::White);
+        assert_eq!(moves[0], Move::castle(Square::from_string("b1"), Square::from_string("g1")));
+        assert_eq!(moves[1], Move::castle(Square::from_string("b1"), Square::from_string("a1")));
+    }
 }
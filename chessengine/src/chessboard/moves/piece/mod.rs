@@ -4,15 +4,48 @@ pub mod pawn;
 pub mod sliding_piece;
 
 use crate::chessboard::Chessboard;
-use super::Move;
+use super::{GenType, Move, MoveList};
 
-use utils::{square::Square, piece::Piece};
+use utils::{color::Color, square::Square, piece::Piece};
+
+// Squares the moves of a piece may go to for a kind of generation. The king
+// and the pawns refine it.
+pub fn gen_targets(chessboard: &Chessboard, color: &Color, gen: GenType) -> u64 {
+    let allies = chessboard.get_colors(color);
+
+    match gen {
+        GenType::All => !allies,
+        GenType::Captures => chessboard.get_colors(&color.opposite()),
+        GenType::Quiets => chessboard.empty_board,
+        GenType::Evasions => chessboard.check_mask(color) & !allies,
+    }
+}
+
+// Moves of every knight or king in `pieces`, looking their attacks up in
+// the `attacks` table.
+pub fn generate_leaper_moves(
+    pieces: u64,
+    chessboard: &Chessboard,
+    targets: u64,
+    attacks: &[u64; 64],
+    moves: &mut MoveList,
+) {
+    let mut bb = pieces;
+
+    while bb != 0 {
+        let square = bb.trailing_zeros();
+
+        sliding_piece::convert_bb_to_moves(chessboard, attacks[square as usize] & targets, Square::from_u32(square), moves);
+
+        bb &= bb - 1;
+    }
+}
 
 pub fn convert_bb_to_moves(
     chessboard: &Chessboard,
     bb: u64,
     relation: i32,
-    moves: &mut Vec<Move>) {
+    moves: &mut MoveList) {
     let mut bb = bb;
 
     while bb != 0 {
@@ -23,7 +56,7 @@ pub fn convert_bb_to_moves(
         let mv = chessboard.generate_move(from, to);
 
         // Handle promotion
-        if let Some(piece) = chessboard.get_piece(&mv.from) {
+        if let Some(piece) = chessboard.get_piece(&mv.from()) {
             if piece == Piece::Pawn {
                 chessboard.generate_promotion_moves(moves, mv);
             }
@@ -49,25 +82,25 @@ mod test {
         let chessboard = Chessboard::new("8/8/8/8/8/8/8/8 w - - 0 1".to_string());
         let bb = 0b00000000;
         let relation = 0;
-        let moves = &mut Vec::new();
+        let moves = &mut MoveList::new();
         convert_bb_to_moves(&chessboard, bb, relation, moves);
         assert_eq!(moves.len(), 0);
 
         let chessboard = Chessboard::new("8/8/8/8/8/8/8/8 w - - 0 1".to_string());
         let bb = 0b00000001_00000000_00000000;
         let relation = direction::SOUT;
-        let moves = &mut Vec::new();
+        let moves = &mut MoveList::new();
         convert_bb_to_moves(&chessboard, bb, relation, moves);
         assert_eq!(moves.len(), 1);
-        assert_eq!(moves[0], Move::new(Square::from_u32(8), Square::from_u32(16)));
+        assert_eq!(moves[0], Move::new(Square::from_u32(8), Square::from_u32(16), Move::QUIET));
 
         let chessboard = Chessboard::new("8/8/8/8/8/8/8/8 w - - 0 1".to_string());
         let bb = 0b10000001_00000000_00000000;
         let relation = direction::SOUT;
-        let moves = &mut Vec::new();
+        let moves = &mut MoveList::new();
         convert_bb_to_moves(&chessboard, bb, relation, moves);
         assert_eq!(moves.len(), 2);
-        assert_eq!(moves[0], Move::new(Square::from_u32(8), Square::from_u32(16)));
-        assert_eq!(moves[1], Move::new(Square::from_u32(15), Square::from_u32(23)));
+        assert_eq!(moves[0], Move::new(Square::from_u32(8), Square::from_u32(16), Move::QUIET));
+        assert_eq!(moves[1], Move::new(Square::from_u32(15), Square::from_u32(23), Move::QUIET));
     }
 }
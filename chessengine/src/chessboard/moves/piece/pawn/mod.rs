@@ -1,9 +1,12 @@
 use crate::chessboard::Chessboard;
 
+use bitboard::attacks::PAWN_ATTACKS;
 use bitboard::patterns::pawn;
+use bitboard::{north_one, south_one};
 
 use utils::color::Color;
 use utils::direction;
+use utils::rank::{self, Rank};
 
 use super::*;
 
@@ -11,27 +14,56 @@ pub fn generate_pseudo_moves(
     pawns: u64,
     chessboard: &Chessboard,
     color: &Color,
-    moves: &mut Vec<Move>,
+    gen: GenType,
+    moves: &mut MoveList,
 ) {
-    let enemies = chessboard.get_colors(&chessboard.turn.opposite());
+    let enemies = chessboard.get_colors(&color.opposite());
+    let promotions = match color {
+        Color::White => rank::RANKS[Rank::Eight as usize],
+        Color::Black => rank::RANKS[Rank::One as usize],
+    };
 
-    moves.append(&mut generate_single_push_moves(pawns, chessboard, color));
-    moves.append(&mut generate_double_push_moves(pawns, chessboard, color));
+    // Pushes promoting to a piece go with the captures.
+    let push_targets = match gen {
+        GenType::All => !0,
+        GenType::Captures => promotions,
+        GenType::Quiets => !promotions,
+        GenType::Evasions => chessboard.check_mask(color),
+    };
 
-    let ep: u64 = match chessboard.en_passant {
-        Some(ep) => ep.to_bitboard(),
-        None => 0,
+    let mut pushes = generate_single_push_moves(pawns, chessboard, color);
+    pushes.extend(generate_double_push_moves(pawns, chessboard, color));
+    pushes.retain(|mv| mv.to().to_bitboard() & push_targets != 0);
+    moves.extend(pushes);
+
+    // Only the side to move may take en passant.
+    let en_passant = chessboard.en_passant.filter(|_| *color == chessboard.turn);
+    let ep: u64 = match (en_passant, gen) {
+        (None, _) | (_, GenType::Quiets) => 0,
+        // The pawn that just moved may be the one giving check.
+        (Some(ep), GenType::Evasions) => {
+            let captured = match color {
+                Color::White => south_one(ep.to_bitboard()),
+                Color::Black => north_one(ep.to_bitboard()),
+            };
+            match (ep.to_bitboard() | captured) & chessboard.check_mask(color) {
+                0 => 0,
+                _ => ep.to_bitboard(),
+            }
+        },
+        (Some(ep), _) => ep.to_bitboard(),
     };
 
-    moves.append(&mut generate_capture_moves(pawns, &(enemies | ep), chessboard, color));
+    let targets = enemies & gen_targets(chessboard, color, gen);
+    moves.extend(generate_capture_moves(pawns, &(targets | ep), chessboard, color));
 }
 
 fn generate_single_push_moves(
     pawns: u64,
     chessboard: &Chessboard,
     color: &Color,
-) -> Vec<Move> {
-    let mut moves = Vec::new();
+) -> MoveList {
+    let mut moves = MoveList::new();
     let single_push_targets: u64 = pawn::single_push_targets(pawns, chessboard.empty_board, color);
     // println!("single_push_targets: {:b}", single_push_targets);
     let direction = if color == &Color::White { direction::SOUT } else { direction::NORT };
@@ -45,8 +77,8 @@ fn generate_double_push_moves(
     pawns: u64,
     chessboard: &Chessboard,
     color: &Color,
-) -> Vec<Move> {
-    let mut moves = Vec::new();
+) -> MoveList {
+    let mut moves = MoveList::new();
     let double_push_targets: u64 = pawn::double_push_targets(pawns, chessboard.empty_board, color);
     let direction = if color == &Color::White { direction::SOUT } else { direction::NORT };
 
@@ -60,17 +92,22 @@ pub fn generate_capture_moves(
     enemies: &u64,
     chessboard: &Chessboard,
     color: &Color,
-) -> Vec<Move> {
-    let west_attack_targets: u64 = pawn::west_attack_targets(pawns, color) & enemies;
-    let east_attack_targets: u64 = pawn::east_attack_targets(pawns, color) & enemies;
-
-    let west_direction = if color == &Color::White { direction::SOEA } else { direction::NOEA };
-    let east_direction = if color == &Color::White { direction::SOWE } else { direction::NOWE };
-
-    let mut moves = Vec::new();
+) -> MoveList {
+    let mut moves = MoveList::new();
+    let mut bb = pawns;
+
+    while bb != 0 {
+        let square = bb.trailing_zeros();
+        let mut targets = PAWN_ATTACKS[color.to_usize()][square as usize] & enemies;
+
+        while targets != 0 {
+            let mv = chessboard.generate_move(Square::from_u32(square), Square::from_u32(targets.trailing_zeros()));
+            chessboard.generate_promotion_moves(&mut moves, mv);
+            targets &= targets - 1;
+        }
 
-    convert_bb_to_moves(chessboard, west_attack_targets, west_direction, &mut moves);
-    convert_bb_to_moves(chessboard, east_attack_targets, east_direction, &mut moves);
+        bb &= bb - 1;
+    }
 
     moves
 }
@@ -95,7 +132,7 @@ mod tests {
         let pawns = chessboard.get_pieces_color(&Piece::Pawn, &color);
         let moves = generate_single_push_moves(pawns, &chessboard, &color);
         assert_eq!(moves.len(), 1);
-        assert_eq!(moves[0], Move::new(Square::from_u32(8), Square::from_u32(16)));
+        assert_eq!(moves[0], Move::new(Square::from_u32(8), Square::from_u32(16), Move::QUIET));
 
         let chessboard = Chessboard::new("8/8/8/8/8/p7/P7/8 w - - 0 1".to_string());
         let color = Color::White;
@@ -108,7 +145,7 @@ mod tests {
         let pawns = chessboard.get_pieces_color(&Piece::Pawn, &color);
         let moves = generate_single_push_moves(pawns, &chessboard, &color);
         assert_eq!(moves.len(), 1);
-        assert_eq!(moves[0], Move::new(Square::from_u32(48), Square::from_u32(40)));
+        assert_eq!(moves[0], Move::new(Square::from_u32(48), Square::from_u32(40), Move::QUIET));
 
         let chessboard = Chessboard::new("rnbqkbnr/1ppppppp/8/p7/P7/8/1PPPPPPP/RNBQKBNR w - a6 0 1".to_string());
         let color = Color::White;
@@ -133,7 +170,7 @@ mod tests {
         let pawns = chessboard.get_pieces_color(&Piece::Pawn, &color);
         let moves = generate_double_push_moves(pawns, &chessboard, &color);
         assert_eq!(moves.len(), 1);
-        assert_eq!(moves[0], Move::new(Square::from_u32(8), Square::from_u32(24)));
+        assert_eq!(moves[0], Move::new(Square::from_u32(8), Square::from_u32(24), Move::DOUBLE_PUSH));
 
         let chessboard = Chessboard::new("8/8/8/8/8/p7/P7/8 w - - 0 1".to_string());
         let color = Color::White;
@@ -173,8 +210,7 @@ mod tests {
         let enemies = chessboard.get_colors(&Color::Black);
         let moves = generate_capture_moves(pawns, &enemies, &chessboard, &color);
         assert_eq!(moves.len(), 1);
-        let mut expected = Move::new(Square::from_u32(8), Square::from_u32(17));
-        expected.capture = Some(Piece::Pawn);
+        let expected = Move::new(Square::from_u32(8), Square::from_u32(17), Move::CAPTURE);
         assert_eq!(moves[0], expected);
 
         let chessboard = Chessboard::new("8/8/8/8/8/p1p5/1P6/8 w - - 0 1".to_string());
@@ -183,11 +219,9 @@ mod tests {
         let enemies = chessboard.get_colors(&Color::Black);
         let moves = generate_capture_moves(pawns, &enemies, &chessboard, &color);
         assert_eq!(moves.len(), 2);
-        let mut expected = Move::new(Square::from_u32(9), Square::from_u32(16));
-        expected.capture = Some(Piece::Pawn);
+        let expected = Move::new(Square::from_u32(9), Square::from_u32(16), Move::CAPTURE);
         assert!(moves.contains(&expected));
-        let mut expected = Move::new(Square::from_u32(9), Square::from_u32(18));
-        expected.capture = Some(Piece::Pawn);
+        let expected = Move::new(Square::from_u32(9), Square::from_u32(18), Move::CAPTURE);
         assert!(moves.contains(&expected));
 
         let chessboard = Chessboard::new("rnbqkbnr/8/8/p7/P7/8/8/RNBQKBNR w - - 0 1".to_string());
@@ -217,8 +251,7 @@ mod tests {
         enemies |= chessboard.en_passant.unwrap().to_bitboard();
         let moves = generate_capture_moves(pawns, &enemies, &chessboard, &color);
         assert_eq!(moves.len(), 1);
-        let mut expected = Move::new(Square::from_u32(33), Square::from_u32(40));
-        expected.capture = Some(Piece::Pawn);
+        let expected = Move::new(Square::from_u32(33), Square::from_u32(40), Move::EN_PASSANT);
         assert_eq!(moves[0], expected);
     }
 
@@ -238,11 +271,11 @@ mod tests {
         let color = chessboard.turn;
         let pawns = chessboard.get_pieces_color(&Piece::Pawn, &color);
         let enemies = chessboard.get_colors(&chessboard.get_opposite_color(&color));
-        let mut moves = Vec::new();
-        generate_pseudo_moves(pawns, &chessboard, &color, &mut moves);
+        let mut moves = MoveList::new();
+        generate_pseudo_moves(pawns, &chessboard, &color, GenType::All, &mut moves);
         moves.sort();
         assert_eq!(moves.len(), expected_moves.len());
-        assert_eq!(moves, expected_moves);
+        assert_eq!(moves.to_vec(), expected_moves);
     }
 
     #[test]
@@ -251,4 +284,30 @@ mod tests {
                                         vec!["b2b3", "b2b4", "c2c3", "c2c4", "d2d3", "d2d4", "e2e3", "e2e4", "f2f3",
                                              "f2f4", "g2g3", "g2g4", "h2h3", "h2h4", "a5b6"]);
     }
+
+    #[test]
+    fn test_generate_for_side_not_to_move() {
+        use super::*;
+        use utils::piece::Piece;
+
+        // Black pawns, white to move: promotions on the first rank, white
+        // pieces to take and no en passant for black.
+        let chessboard = Chessboard::new("4k3/8/8/8/3pP3/8/1p6/2N1K3 w - e3 0 1".to_string());
+        let pawns = chessboard.get_pieces_color(&Piece::Pawn, &Color::Black);
+
+        for (gen, expected) in [(GenType::All, 9), (GenType::Captures, 8), (GenType::Quiets, 1), (GenType::Evasions, 9)] {
+            let mut moves = MoveList::new();
+            generate_pseudo_moves(pawns, &chessboard, &Color::Black, gen, &mut moves);
+            assert_eq!(moves.len(), expected, "{:?} {:?}", gen, moves);
+            assert!(!moves.iter().any(|mv| mv.is_en_passant()));
+        }
+
+        // The black king is in check from the knight: only taking it helps.
+        let chessboard = Chessboard::new("8/8/8/8/8/3k4/1p6/2N1K3 w - - 0 1".to_string());
+        let pawns = chessboard.get_pieces_color(&Piece::Pawn, &Color::Black);
+        let mut moves = MoveList::new();
+        generate_pseudo_moves(pawns, &chessboard, &Color::Black, GenType::Evasions, &mut moves);
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|mv| mv.to() == Square::from_string("c1")));
+    }
 }
@@ -1,26 +1,19 @@
 use bitboard::bit_scan_forward;
-use utils::square::Square;
+use utils::{color::Color, square::Square};
 
 use crate::chessboard::Chessboard;
 
-use super::Move;
+use super::{gen_targets, GenType, MoveList};
 
 pub mod bishop;
 pub mod rook;
 pub mod queen;
 
-pub struct SlidingPiece {
-    pub mask: u64,
-    pub init: u64,
-    pub step: usize,
-    pub directions: Vec<fn(u64) -> u64>,
-}
-
 pub fn convert_bb_to_moves(
     chessboard: &Chessboard,
     bb: u64,
     from: Square,
-    moves: &mut Vec<Move>,
+    moves: &mut MoveList,
 ) {
     let mut bb: u64 = bb;
 
@@ -33,45 +26,25 @@ pub fn convert_bb_to_moves(
     }
 }
 
-impl Chessboard {
-    pub fn generate_sliding_piece_moves(&self,
-                                        square: usize,
-                                        allies: u64,
-                                        sliding: &SlidingPiece,
-                                        moves: &mut Vec<Move>
-                                       ) {
-        let mask = sliding.mask;
-        let init = sliding.init & !allies;
-        let step = sliding.step;
-        let directions = &sliding.directions;
-        let from = Square::from_u32(square as u32);
-
-        convert_bb_to_moves(self, init, from, moves);
-
-        for direction in directions {
-            let mut ray = init & self.empty_board;
-
-            for _ in 0..step {
-                ray = direction(ray);
-
-                ray &= mask;
-
-                ray &= !allies;
-
-                if ray != 0 {
-                    moves.push(self.convert_bb_to_move(from, ray));
-                }
+// Moves of every slider in `pieces`, looking their targets up in the
+// `attacks` table.
+pub fn generate_slider_moves(
+    pieces: u64,
+    chessboard: &Chessboard,
+    color: &Color,
+    gen: GenType,
+    attacks: fn(usize, u64) -> u64,
+    moves: &mut MoveList,
+) {
+    let mut bb = pieces;
+    let targets = gen_targets(chessboard, color, gen);
+    let occupancy = !chessboard.empty_board;
 
-                ray &= self.empty_board;
+    while bb != 0 {
+        let square = bit_scan_forward(bb) as usize;
 
-                if ray == 0 {
-                    break;
-                }
-            }
-        }
-    }
+        convert_bb_to_moves(chessboard, attacks(square, occupancy) & targets, Square::from_u32(square as u32), moves);
 
-    pub fn convert_bb_to_move(&self, from: Square, bb: u64) -> Move {
-        self.generate_move(from, Square::from_u32(bit_scan_forward(bb)))
+        bb ^= 1 << square;
     }
 }
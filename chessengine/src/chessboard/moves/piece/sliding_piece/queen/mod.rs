@@ -1,15 +1,14 @@
+use bitboard::magic::queen_attacks;
 use utils::color::Color;
 
-use crate::chessboard::{Chessboard, moves::Move};
-
-use super::{bishop, rook};
+use crate::chessboard::{Chessboard, moves::{GenType, list::MoveList}};
 
 pub fn generate_pseudo_moves(
     queens: u64,
     chessboard: &Chessboard,
     color: &Color,
-    moves: &mut Vec<Move>,
+    gen: GenType,
+    moves: &mut MoveList,
 ) {
-    bishop::generate_pseudo_moves(queens, chessboard, color, moves);
-    rook::generate_pseudo_moves(queens, chessboard, color, moves);
+    super::generate_slider_moves(queens, chessboard, color, gen, queen_attacks, moves);
 }
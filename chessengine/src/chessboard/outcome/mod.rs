@@ -0,0 +1,195 @@
+use std::fmt;
+
+use bitboard::{DARK_SQUARES, LIGHT_SQUARES};
+
+use utils::color::Color;
+use utils::piece::Piece;
+
+use super::Chessboard;
+use super::moves::{GenType, Move};
+
+// Which positions count as a draw by insufficient material. FIDE only
+// draws positions where no sequence of legal moves can mate, USCF also
+// draws two knights against a bare king.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MaterialRule {
+    Fide,
+    Uscf,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Outcome {
+    // Holds the winner.
+    Checkmate(Color),
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoves,
+    Repetition,
+}
+
+impl Outcome {
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate(winner) => Some(*winner),
+            _ => None,
+        }
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.winner().is_none()
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Checkmate(Color::White) => write!(f, "checkmate, white wins"),
+            Outcome::Checkmate(Color::Black) => write!(f, "checkmate, black wins"),
+            Outcome::Stalemate => write!(f, "stalemate"),
+            Outcome::InsufficientMaterial => write!(f, "draw by insufficient material"),
+            Outcome::FiftyMoves => write!(f, "draw by the fifty-move rule"),
+            Outcome::Repetition => write!(f, "draw by threefold repetition"),
+        }
+    }
+}
+
+impl Chessboard {
+    // Whether the game is over, and how. None while it goes on.
+    pub fn outcome(&mut self) -> Option<Outcome> {
+        let moves = self.generate_legal_moves(GenType::All);
+
+        self.outcome_with_legal_moves(&moves)
+    }
+
+    // Same as `outcome` for callers that already generated the legal moves.
+    pub fn outcome_with_legal_moves(&self, legal_moves: &[Move]) -> Option<Outcome> {
+        // Mate ends the game even if the move also reached a draw condition.
+        if legal_moves.is_empty() {
+            return match self.is_in_check(&self.turn) {
+                true => Some(Outcome::Checkmate(self.turn.opposite())),
+                false => Some(Outcome::Stalemate),
+            };
+        }
+
+        if self.is_insufficient_material() {
+            Some(Outcome::InsufficientMaterial)
+        } else if self.is_fifty_move_draw() {
+            Some(Outcome::FiftyMoves)
+        } else if self.is_repetition() {
+            Some(Outcome::Repetition)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_insufficient_material(&self) -> bool {
+        let colors = [Color::White, Color::Black];
+
+        if colors.iter().any(|color| {
+            self.get_pieces_color(&Piece::Pawn, color)
+                | self.get_pieces_color(&Piece::Rook, color)
+                | self.get_pieces_color(&Piece::Queen, color) != 0
+        }) {
+            return false;
+        }
+
+        let knights = colors.map(|color| self.get_pieces_color(&Piece::Knight, &color).count_ones());
+        let bishops = self.get_pieces_color(&Piece::Bishop, &Color::White)
+            | self.get_pieces_color(&Piece::Bishop, &Color::Black);
+        let minors = knights[0] + knights[1] + bishops.count_ones();
+
+        // A single minor piece cannot mate, nor can bishops that all stand
+        // on squares of the same colour.
+        if minors <= 1 {
+            return true;
+        }
+        if knights == [0, 0] && (bishops & LIGHT_SQUARES == 0 || bishops & DARK_SQUARES == 0) {
+            return true;
+        }
+
+        // Two knights cannot force mate on a bare king.
+        self.material_rule == MaterialRule::Uscf
+            && bishops == 0
+            && (knights == [2, 0] || knights == [0, 2])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_outcome(fen: &str, expected: Option<Outcome>) {
+        let mut chessboard = Chessboard::new(fen.to_string());
+        assert_eq!(chessboard.outcome(), expected, "{}", fen);
+    }
+
+    #[test]
+    fn test_outcomes() {
+        test_outcome("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", None);
+
+        // Fool's mate and back rank mate.
+        test_outcome("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3", Some(Outcome::Checkmate(Color::Black)));
+        test_outcome("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", Some(Outcome::Checkmate(Color::White)));
+
+        test_outcome("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", Some(Outcome::Stalemate));
+
+        test_outcome("8/8/4k3/8/8/3K4/8/8 w - - 0 1", Some(Outcome::InsufficientMaterial));
+        test_outcome("8/8/4k3/8/8/3KN3/8/8 w - - 0 1", Some(Outcome::InsufficientMaterial));
+        test_outcome("8/8/4k3/8/8/3KB3/8/8 b - - 0 1", Some(Outcome::InsufficientMaterial));
+        test_outcome("8/8/4k3/8/8/3KP3/8/8 w - - 0 1", None);
+        test_outcome("8/8/4kn2/8/8/3KB3/8/8 w - - 0 1", None);
+
+        test_outcome("4k3/8/8/8/8/8/8/R3K3 w - - 100 80", Some(Outcome::FiftyMoves));
+        // Mate on the hundredth half move is still mate.
+        test_outcome("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 100 80", Some(Outcome::Checkmate(Color::White)));
+    }
+
+    fn test_insufficient_material(fen: &str, rule: MaterialRule, expected: bool) {
+        let mut chessboard = Chessboard::new(fen.to_string());
+        chessboard.material_rule = rule;
+        assert_eq!(chessboard.is_insufficient_material(), expected, "{} {:?}", fen, rule);
+    }
+
+    #[test]
+    fn test_is_insufficient_material() {
+        for rule in [MaterialRule::Fide, MaterialRule::Uscf] {
+            test_insufficient_material("8/8/4k3/8/8/3K4/8/8 w - - 0 1", rule, true);
+            test_insufficient_material("8/8/4k3/8/8/3KN3/8/8 w - - 0 1", rule, true);
+            test_insufficient_material("8/8/4kb2/8/8/3K4/8/8 w - - 0 1", rule, true);
+
+            // Bishops on squares of one colour, whoever owns them.
+            test_insufficient_material("8/8/4kb2/8/8/3KB3/8/8 w - - 0 1", rule, true);
+            test_insufficient_material("8/8/4k3/8/8/1B1KB3/8/8 w - - 0 1", rule, false);
+            test_insufficient_material("8/8/4k3/8/8/B2KB3/8/8 w - - 0 1", rule, true);
+            test_insufficient_material("8/8/4k1b1/8/8/3KB3/8/8 w - - 0 1", rule, false);
+
+            test_insufficient_material("8/8/4kn2/8/8/3KN3/8/8 w - - 0 1", rule, false);
+            test_insufficient_material("8/8/4kn2/8/8/3KB3/8/8 w - - 0 1", rule, false);
+            test_insufficient_material("8/8/4k3/8/8/3KBN2/8/8 w - - 0 1", rule, false);
+            test_insufficient_material("8/8/4k3/8/8/3KP3/8/8 w - - 0 1", rule, false);
+            test_insufficient_material("8/8/4k3/8/8/3KR3/8/8 w - - 0 1", rule, false);
+        }
+
+        test_insufficient_material("8/8/4k3/8/8/2NKN3/8/8 w - - 0 1", MaterialRule::Fide, false);
+        test_insufficient_material("8/8/4k3/8/8/2NKN3/8/8 w - - 0 1", MaterialRule::Uscf, true);
+        test_insufficient_material("8/8/4kn2/8/8/2NKN3/8/8 w - - 0 1", MaterialRule::Uscf, false);
+    }
+
+    #[test]
+    fn test_outcome_repetition() {
+        let mut chessboard = Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
+
+        for _ in 0..2 {
+            for mv in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                assert_eq!(chessboard.outcome(), None);
+                let mv = chessboard.generate_move_from_string(mv.to_string());
+                chessboard.make_move(&mv);
+            }
+        }
+
+        let outcome = chessboard.outcome();
+        assert_eq!(outcome, Some(Outcome::Repetition));
+        assert!(outcome.unwrap().is_draw());
+        assert_eq!(outcome.unwrap().winner(), None);
+    }
+}
@@ -1,18 +1,103 @@
 use std::{fs, println};
+use std::time::Instant;
 
-use super::{Chessboard, moves::piece::king::generate_castling_moves};
+use super::Chessboard;
+use super::epd::Epd;
+use super::moves::GenType;
 
 pub fn run_perft(file_path: &String) {
     let contents = fs::read_to_string(file_path)
         .expect("Something went wrong reading the file");
 
+    if file_path.ends_with(".epd") {
+        run_perft_epd(&contents);
+        return;
+    }
+
     let mut chessboard = Chessboard::new(contents);
+    if let Err(errors) = chessboard.validate() {
+        for error in errors {
+            println!("Invalid position: {}", error);
+        }
+        return;
+    }
     //chessboard.pretty_print();
     let nodes = chessboard.perft();
     //chessboard.show_hashmap();
     println!("{}", nodes);
 }
 
+// Check the perft counts of each line, one D operation at a time.
+fn run_perft_epd(contents: &str) {
+    let mut failures = 0;
+
+    for (index, line) in contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let epd = match Epd::parse(line) {
+            Ok(epd) => epd,
+            Err(err) => {
+                println!("Invalid EPD on line {}: {}", index + 1, err);
+                failures += 1;
+                continue;
+            },
+        };
+        let name = epd.id.clone().unwrap_or_else(|| epd.chessboard.to_fen());
+
+        if let Err(errors) = epd.chessboard.validate() {
+            for error in errors {
+                println!("Invalid position on line {}: {}", index + 1, error);
+            }
+            failures += 1;
+            continue;
+        }
+
+        for (depth, expected, nodes) in epd_perft(&epd) {
+            match nodes == expected {
+                true => println!("{} D{}: {}", name, depth, nodes),
+                false => {
+                    println!("{} D{}: {}, expected {}", name, depth, nodes, expected);
+                    failures += 1;
+                },
+            }
+        }
+    }
+
+    println!("{} failures", failures);
+}
+
+// Depth, expected and actual count for each D operation.
+pub fn epd_perft(epd: &Epd) -> Vec<(u8, u64, u64)> {
+    epd.perft.iter().map(|(depth, expected)| {
+        let mut chessboard = epd.chessboard.clone();
+        chessboard.perft_depth = *depth;
+        (*depth, *expected, chessboard.perft())
+    }).collect()
+}
+
+// Positions and depths of the benchmark, a few seconds in release mode.
+const BENCH: [(&str, u8); 3] = [
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 6),
+    ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 5),
+    ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 6),
+];
+
+pub fn run_bench() {
+    let mut total_nodes = 0;
+    let start = Instant::now();
+
+    for (fen, depth) in BENCH {
+        let mut chessboard = Chessboard::new(format!("{} {}", fen, depth));
+        let position_start = Instant::now();
+        let nodes = chessboard.perft();
+        let elapsed = position_start.elapsed().as_secs_f64();
+
+        println!("{} depth {}: {} nodes, {:.0} nps", fen, depth, nodes, nodes as f64 / elapsed);
+        total_nodes += nodes;
+    }
+
+    let elapsed = start.elapsed().as_secs_f64();
+    println!("Total: {} nodes in {:.3}s, {:.0} nps", total_nodes, elapsed, total_nodes as f64 / elapsed);
+}
+
 impl Chessboard {
     pub fn perft(&mut self) -> u64 {
         //self.pretty_print();
@@ -23,21 +108,23 @@ impl Chessboard {
 
         let mut nodes = 0;
 
-        let mut moves = self.generate_moves();
-        moves.append(&mut generate_castling_moves(self, &self.turn));
+        let moves = self.generate_legal_moves(GenType::All);
+
+        // Moves are legal, the last ply only needs to be counted.
+        if self.perft_depth == 1 {
+            return moves.len() as u64;
+        }
 
         for m in moves {
             self.make_move(&m);
 
-            if !self.is_making_check(&self.turn) {
-                // let mut count = self.mv_hashmap.get(&m.to_string());
-                // if count == None {
-                //     self.mv_hashmap.insert(m.to_string(), 0);
-                //     count = Some(&0);
-                // }
-                // self.mv_hashmap.insert(m.to_string(), count.unwrap() + 1);
-                nodes += self.perft();
-            }
+            // let mut count = self.mv_hashmap.get(&m.to_string());
+            // if count == None {
+            //     self.mv_hashmap.insert(m.to_string(), 0);
+            //     count = Some(&0);
+            // }
+            // self.mv_hashmap.insert(m.to_string(), count.unwrap() + 1);
+            nodes += self.perft();
 
             self.unmake_move(&m);
         }
@@ -59,91 +146,105 @@ impl Chessboard {
 
 #[cfg(test)]
 mod tests {
-    use std::assert_eq;
-
     use super::*;
 
-    fn test_perft(fen: &str, expected: u64, show_debug: bool) {
-        let mut chessboard = Chessboard::new(String::from(fen));
+    const SUITE: &str = include_str!("../../../resources/perft_files/suite.epd");
 
-        if show_debug {
-            chessboard.pretty_print();
-        }
+    // Positions of the suite whose id, up to its first space, is `group`.
+    fn suite(group: &str) -> Vec<Epd> {
+        SUITE.lines()
+            .map(|line| Epd::parse(line).unwrap())
+            .filter(|epd| epd.id.as_deref().and_then(|id| id.split(' ').next()) == Some(group))
+            .collect()
+    }
 
-        let actual = chessboard.perft();
+    fn test_perft(group: &str) {
+        let epds = suite(group);
+        assert!(!epds.is_empty(), "No position in {}", group);
 
-        if show_debug {
-            chessboard.show_hashmap();
+        for epd in epds {
+            let name = epd.id.clone().unwrap();
+
+            for (depth, expected, nodes) in epd_perft(&epd) {
+                assert_eq!(nodes, expected, "{} D{}", name, depth);
+
+                // The same position with colours swapped must give the same count.
+                let mut mirrored = epd.chessboard.mirrored();
+                mirrored.perft_depth = depth;
+                assert_eq!(mirrored.perft(), expected, "Mirrored {} D{}", name, depth);
+            }
         }
+    }
 
-        assert_eq!(actual, expected);
+    #[test]
+    fn fen_round_trip() {
+        for line in SUITE.lines() {
+            let epd = Epd::parse(line).unwrap();
+            let mut chessboard = epd.chessboard.clone();
+            assert_eq!(chessboard.validate(), Ok(()), "{}", line);
+
+            // Chess960 castling rights are written with the king and queen
+            // side letters.
+            if !chessboard.chess960 {
+                assert_eq!(epd.to_epd(), line);
+            }
+
+            // Positions one move deep must survive the trip as well.
+            for mv in chessboard.generate_legal_moves(GenType::All) {
+                chessboard.make_move(&mv);
+                let copy = Chessboard::from_fen(&chessboard.to_fen()).unwrap();
+                assert_eq!(copy.to_fen(), chessboard.to_fen(), "{} {}", line, mv.to_string());
+                assert_eq!(copy.hash(), chessboard.hash(), "{} {}", line, mv.to_string());
+                chessboard.unmake_move(&mv);
+            }
+        }
     }
 
     #[test]
     #[ignore]
     fn base() {
-        test_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 0", 1, false);
-        test_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 1", 20, false);
-        test_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 2", 400, false);
-        test_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 3", 8902, false);
-        test_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 4", 197281, false);
-        test_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 5", 4865609, false);
+        test_perft("base");
     }
 
     #[test]
     #[ignore]
     fn kiwipete() {
-        test_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 1", 48, false);
-        test_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 2", 2039, false);
-        test_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 3", 97862, false);
+        test_perft("kiwipete");
     }
 
     #[test]
     #[ignore]
     fn forbidden_castling() {
-        test_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 1", 14, false);
-        test_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 2", 191, false);
-        test_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 3", 2812, false);
-        //test_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 4", 43238, false);
-        //test_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 5", 674624, false);
+        test_perft("forbidden_castling");
     }
 
     #[test]
     #[ignore]
     fn mirrored() {
-        test_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1 1", 6, false);
-        test_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1 2", 264, false);
-        test_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1 3", 9467, false);
-
-        test_perft("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1 1", 6, false);
-        test_perft("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1 2", 264, false);
-        test_perft("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1 3", 9467, false);
+        test_perft("mirrored");
     }
 
     #[test]
     #[ignore]
     fn edwards() {
-        test_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8 1", 44, false);
-        test_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8 2", 1486, false);
-        test_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8 3", 62379, false);
+        test_perft("edwards");
     }
 
     #[test]
     #[ignore]
     fn edwards_bis() {
-        test_perft("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10 1", 46, false);
-        test_perft("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10 2", 2079, false);
-        test_perft("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10 3", 89890, false);
+        test_perft("edwards_bis");
     }
 
     #[test]
     #[ignore]
     fn misc() {
-        test_perft("rnbqkbnr/ppppp2pp/8/5p2/8/2P5/PP1PPPPP/RNBQKBNR w KQkq f6 2 2 1", 21, false);
-        test_perft("rnbqkbnr/1pppppp1/p7/7p/8/P1P5/1P1PPPPP/RNBQKBNR w KQkq h6 4 3 1", 20, false);
-        test_perft("rnbqkbnr/1ppp1ppp/p7/4p3/8/PP6/2PPPPPP/RNBQKBNR w KQkq e6 4 3 1", 19, false);
-        test_perft("rnbqk1nr/pppp1ppp/4p3/8/Qb6/2P5/PP1PPPPP/RNB1KBNR w KQkq - 4 3 1", 29, false);
-        test_perft("rnbqkbnr/2pppppp/p7/Pp6/8/8/1PPPPPPP/RNBQKBNR w KQkq b6 4 3 1", 22, false);
-        test_perft("r3k3/p1ppqpb1/bn2pnp1/3PN3/1p2P2r/5Q1p/PPPBBPPP/RN2K2R w KQq - 2 2 1", 49, false);
+        test_perft("misc");
+    }
+
+    #[test]
+    #[ignore]
+    fn chess960() {
+        test_perft("chess960");
     }
 }
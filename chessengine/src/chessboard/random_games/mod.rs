@@ -0,0 +1,53 @@
+use super::Chessboard;
+use super::moves::GenType;
+use super::moves::list::MoveList;
+
+// Positions with castling, en passant, promotions and pins, one of them
+// Chess960.
+const FENS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+];
+
+// xorshift64, enough to pick moves.
+pub struct Random(u64);
+
+impl Random {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+// Plays `games` random games of at most `plies` plies from each position,
+// calling `visit` on every position reached with its legal moves, the last
+// one of a game included.
+pub fn play_random_games<F>(games: usize, plies: usize, seed: u64, mut visit: F)
+where
+    F: FnMut(&mut Chessboard, &MoveList, &mut Random),
+{
+    let mut random = Random(seed);
+
+    for fen in FENS {
+        for _ in 0..games {
+            let mut chessboard = Chessboard::new(fen.to_string());
+
+            for _ in 0..plies {
+                let moves = chessboard.generate_legal_moves(GenType::All);
+                visit(&mut chessboard, &moves, &mut random);
+
+                if moves.is_empty() {
+                    break;
+                }
+
+                chessboard.make_move(&moves[random.next() as usize % moves.len()]);
+            }
+        }
+    }
+}
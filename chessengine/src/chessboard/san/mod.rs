@@ -0,0 +1,324 @@
+use std::fmt;
+
+use utils::file::File;
+use utils::piece::Piece;
+use utils::rank::Rank;
+use utils::square::Square;
+
+use super::Chessboard;
+use super::check::CheckInfo;
+use super::moves::{GenType, Move};
+
+// Each error holds the move as it was written.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum SanError {
+    InvalidSyntax(String),
+    // No legal move matches.
+    IllegalMove(String),
+    // Several legal moves match, the move lacks disambiguation.
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::InvalidSyntax(san) => write!(f, "invalid SAN move {}", san),
+            SanError::IllegalMove(san) => write!(f, "illegal move {}", san),
+            SanError::AmbiguousMove(san) => write!(f, "ambiguous move {}", san),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+fn piece_from_char(c: char) -> Option<Piece> {
+    match c {
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+fn file_from_char(c: char) -> Option<File> {
+    match c {
+        'a'..='h' => Some(File::from_char(c)),
+        _ => None,
+    }
+}
+
+fn rank_from_char(c: char) -> Option<Rank> {
+    match c {
+        '1'..='8' => Some(Rank::from_char(c)),
+        _ => None,
+    }
+}
+
+// What a SAN move says about the move it stands for.
+struct SanMove {
+    piece: Piece,
+    from_file: Option<File>,
+    from_rank: Option<Rank>,
+    to: Square,
+    promotion: Option<Piece>,
+}
+
+fn parse_san_move(san: &str) -> Option<SanMove> {
+    let mut chars: Vec<char> = san.chars().collect();
+
+    let promotion = match chars.as_slice() {
+        [.., '=', c] | [.., 'a'..='h', '1' | '8', c] => {
+            let piece = piece_from_char(*c).filter(|piece| *piece != Piece::King)?;
+            chars.truncate(chars.len() - 1);
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+            Some(piece)
+        },
+        _ => None,
+    };
+
+    let piece = match chars.first().and_then(|c| piece_from_char(*c)) {
+        Some(piece) => {
+            chars.remove(0);
+            piece
+        },
+        None => Piece::Pawn,
+    };
+
+    if chars.len() < 2 {
+        return None;
+    }
+    let rank = chars.pop().and_then(rank_from_char)?;
+    let file = chars.pop().and_then(file_from_char)?;
+
+    if chars.last() == Some(&'x') {
+        chars.pop();
+    }
+
+    let (from_file, from_rank) = match chars.as_slice() {
+        [] => (None, None),
+        [c @ 'a'..='h'] => (file_from_char(*c), None),
+        [c @ '1'..='8'] => (None, rank_from_char(*c)),
+        [f, r] => (Some(file_from_char(*f)?), Some(rank_from_char(*r)?)),
+        _ => return None,
+    };
+
+    if promotion.is_some() && piece != Piece::Pawn {
+        return None;
+    }
+
+    Some(SanMove { piece, from_file, from_rank, to: Square::new(file, rank), promotion })
+}
+
+impl Chessboard {
+    // Standard algebraic notation of a legal move, with the check and mate
+    // suffixes. Checking moves are played to tell mate apart.
+    pub fn move_to_san(&mut self, mv: &Move) -> String {
+        let check_info = self.compute_check_info();
+        self.move_to_san_with(mv, &check_info)
+    }
+
+    // Several moves of this position, the check information computed once.
+    pub fn moves_to_san(&mut self, moves: &[Move]) -> Vec<String> {
+        let check_info = self.compute_check_info();
+        moves.iter().map(|mv| self.move_to_san_with(mv, &check_info)).collect()
+    }
+
+    fn move_to_san_with(&mut self, mv: &Move, check_info: &CheckInfo) -> String {
+        let mut san = self.move_to_san_without_suffix(mv);
+
+        if self.gives_check(mv, check_info) {
+            self.make_move(mv);
+            match self.generate_legal_moves(GenType::All).is_empty() {
+                true => san.push('#'),
+                false => san.push('+'),
+            }
+            self.unmake_move(mv);
+        }
+
+        san
+    }
+
+    fn move_to_san_without_suffix(&self, mv: &Move) -> String {
+        if mv.is_castling() {
+            return match mv.is_queen_castle() {
+                true => String::from("O-O-O"),
+                false => String::from("O-O"),
+            };
+        }
+
+        let from = mv.from();
+        let to = mv.to();
+        let piece = self.get_piece(&from).expect("No piece on the origin square");
+        let mut san = String::new();
+
+        if piece == Piece::Pawn {
+            if mv.is_capture() {
+                san.push(from.file.to_char());
+            }
+        } else {
+            san.push(piece.to_char().to_ascii_uppercase());
+
+            // Other pieces of the same kind that may go to the same square.
+            let rivals: Vec<Square> = self.generate_legal_moves(GenType::All).iter()
+                .filter(|other| !other.is_castling() && other.to() == to && other.from() != from)
+                .filter(|other| self.get_piece(&other.from()) == Some(piece))
+                .map(|other| other.from())
+                .collect();
+
+            if !rivals.is_empty() {
+                if rivals.iter().all(|square| square.file != from.file) {
+                    san.push(from.file.to_char());
+                } else if rivals.iter().all(|square| square.rank != from.rank) {
+                    san.push(from.rank.to_char());
+                } else {
+                    san.push_str(&from.to_string());
+                }
+            }
+        }
+
+        if mv.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&to.to_string());
+
+        if let Some(promotion) = mv.promotion() {
+            san.push('=');
+            san.push(promotion.to_char().to_ascii_uppercase());
+        }
+
+        san
+    }
+
+    // Legal move written in standard algebraic notation. Check and
+    // annotation suffixes are ignored, castling may be written with zeros and
+    // the promotion without '='.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let error = |make: fn(String) -> SanError| make(san.to_string());
+        let trimmed = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let moves = self.generate_legal_moves(GenType::All);
+
+        let candidates: Vec<Move> = match trimmed {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let queen_side = trimmed.len() == 5;
+                moves.iter()
+                    .filter(|mv| mv.is_castling() && mv.is_queen_castle() == queen_side)
+                    .copied()
+                    .collect()
+            },
+            _ => {
+                let parsed = parse_san_move(trimmed).ok_or_else(|| error(SanError::InvalidSyntax))?;
+                moves.iter()
+                    .filter(|mv| !mv.is_castling() && mv.to() == parsed.to)
+                    .filter(|mv| self.get_piece(&mv.from()) == Some(parsed.piece))
+                    .filter(|mv| parsed.from_file.is_none_or(|file| mv.from().file == file))
+                    .filter(|mv| parsed.from_rank.is_none_or(|rank| mv.from().rank == rank))
+                    .filter(|mv| mv.promotion() == parsed.promotion)
+                    .copied()
+                    .collect()
+            },
+        };
+
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(error(SanError::IllegalMove)),
+            _ => Err(error(SanError::AmbiguousMove)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uci_to_san(fen: &str, uci: &str) -> String {
+        let mut chessboard = Chessboard::new(fen.to_string());
+        let mv = chessboard.generate_legal_moves(GenType::All).into_iter()
+            .find(|mv| chessboard.move_to_uci(mv) == uci)
+            .unwrap();
+        chessboard.move_to_san(&mv)
+    }
+
+    #[test]
+    fn test_move_to_san() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(uci_to_san(start, "e2e4"), "e4");
+        assert_eq!(uci_to_san(start, "g1f3"), "Nf3");
+
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(uci_to_san(kiwipete, "e1g1"), "O-O");
+        assert_eq!(uci_to_san(kiwipete, "e1c1"), "O-O-O");
+        assert_eq!(uci_to_san(kiwipete, "d5e6"), "dxe6");
+        assert_eq!(uci_to_san(kiwipete, "e5f7"), "Nxf7");
+        assert_eq!(uci_to_san(kiwipete, "f3f6"), "Qxf6");
+
+        // Disambiguation by file, then by rank, then by both.
+        assert_eq!(uci_to_san("4k3/8/8/8/R6R/8/8/4K3 w - - 0 1", "a4d4"), "Rad4");
+        assert_eq!(uci_to_san("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+        assert_eq!(uci_to_san("4k3/8/8/8/Q6Q/8/8/K6Q w - - 0 1", "h4e4"), "Qh4e4+");
+        // A pinned rival does not count.
+        assert_eq!(uci_to_san("4r2k/8/8/8/4R3/8/R7/4K3 w - - 0 1", "a2a4"), "Ra4");
+
+        assert_eq!(uci_to_san("8/4P3/8/8/8/8/8/k1K5 w - - 0 1", "e7e8q"), "e8=Q");
+        assert_eq!(uci_to_san("3r4/4P3/8/8/8/8/8/k1K5 w - - 0 1", "e7d8n"), "exd8=N");
+        assert_eq!(uci_to_san("4k3/8/8/8/8/8/8/4K2R w - - 0 1", "h1h8"), "Rh8+");
+        assert_eq!(uci_to_san("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8#");
+    }
+
+    #[test]
+    fn test_parse_san() {
+        let chessboard = Chessboard::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".to_string());
+
+        let parsed = |san: &str| chessboard.parse_san(san).map(|mv| chessboard.move_to_uci(&mv));
+        assert_eq!(parsed("O-O"), Ok(String::from("e1g1")));
+        assert_eq!(parsed("0-0-0"), Ok(String::from("e1c1")));
+        assert_eq!(parsed("dxe6"), Ok(String::from("d5e6")));
+        assert_eq!(parsed("Nxf7+"), Ok(String::from("e5f7")));
+        assert_eq!(parsed("Qf3xf6!"), Ok(String::from("f3f6")));
+        assert_eq!(parsed("a4"), Ok(String::from("a2a4")));
+
+        assert_eq!(parsed("Nb5"), Ok(String::from("c3b5")));
+        assert_eq!(parsed("Nd3"), Ok(String::from("e5d3")));
+        assert_eq!(parsed("Ng4"), Ok(String::from("e5g4")));
+        assert_eq!(parsed("Rb1"), Ok(String::from("a1b1")));
+
+        assert_eq!(parsed("Ke3"), Err(SanError::IllegalMove(String::from("Ke3"))));
+        assert_eq!(parsed("e9"), Err(SanError::InvalidSyntax(String::from("e9"))));
+        assert_eq!(parsed("Nx"), Err(SanError::InvalidSyntax(String::from("Nx"))));
+
+        let chessboard = Chessboard::new("4k3/8/8/8/R6R/8/8/4K3 w - - 0 1".to_string());
+        assert_eq!(chessboard.parse_san("Rd4"), Err(SanError::AmbiguousMove(String::from("Rd4"))));
+        assert!(chessboard.parse_san("Rhd4").is_ok());
+
+        let chessboard = Chessboard::new("3r4/4P3/8/8/8/8/8/k1K5 w - - 0 1".to_string());
+        assert_eq!(chessboard.parse_san("exd8=N").unwrap().promotion(), Some(Piece::Knight));
+        assert_eq!(chessboard.parse_san("e8Q").unwrap().promotion(), Some(Piece::Queen));
+        assert!(chessboard.parse_san("exd8").is_err());
+    }
+
+    // Every legal move gives back the same move through its SAN.
+    #[test]
+    fn test_san_round_trip() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        ];
+
+        for fen in fens {
+            let mut chessboard = Chessboard::new(fen.to_string());
+            let moves = chessboard.generate_legal_moves(GenType::All);
+            let sans = chessboard.moves_to_san(&moves);
+
+            for (mv, san) in moves.iter().zip(sans) {
+                assert_eq!(chessboard.move_to_san(mv), san, "{}", fen);
+                assert_eq!(chessboard.parse_san(&san), Ok(*mv), "{} in {}", san, fen);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use bitboard::{flip_vertical, mirror_horizontal};
+
+use utils::color::Color;
+use utils::file::File;
+use utils::rank::Rank;
+use utils::square::Square;
+
+use super::Chessboard;
+
+impl Chessboard {
+    // Same position with the colours swapped: the board is flipped
+    // vertically and the other side is to move. Perft counts and
+    // evaluations must not change.
+    pub fn mirrored(&self) -> Chessboard {
+        let mut chessboard = self.clone();
+
+        chessboard.piece_board = self.piece_board.iter().map(|bb| flip_vertical(*bb)).collect();
+        chessboard.color_board = vec![
+            flip_vertical(self.color_board[Color::Black.to_usize()]),
+            flip_vertical(self.color_board[Color::White.to_usize()]),
+        ];
+        chessboard.empty_board = flip_vertical(self.empty_board);
+        chessboard.turn = self.turn.opposite();
+        chessboard.white_castle = self.black_castle;
+        chessboard.black_castle = self.white_castle;
+        chessboard.en_passant = self.en_passant
+            .map(|square| Square::new(square.file, Rank::from_u32(7 - square.rank as u32)));
+
+        chessboard.reset_derived_state();
+        chessboard
+    }
+
+    // Same position seen in a mirror, a-file and h-file swapped. Castling
+    // rights are dropped since castling is not symmetric that way.
+    pub fn flipped_horizontal(&self) -> Chessboard {
+        let mut chessboard = self.clone();
+
+        chessboard.piece_board = self.piece_board.iter().map(|bb| mirror_horizontal(*bb)).collect();
+        chessboard.color_board = self.color_board.iter().map(|bb| mirror_horizontal(*bb)).collect();
+        chessboard.empty_board = mirror_horizontal(self.empty_board);
+        chessboard.white_castle = (None, None);
+        chessboard.black_castle = (None, None);
+        chessboard.en_passant = self.en_passant
+            .map(|square| Square::new(File::from_u32(7 - square.file as u32), square.rank));
+
+        chessboard.reset_derived_state();
+        chessboard
+    }
+
+    // Rebuild what follows from the bitboards. The history does not belong
+    // to the new position.
+    fn reset_derived_state(&mut self) {
+        self.rebuild_mailbox();
+        self.history.clear();
+        self.hash = self.compute_hash();
+        self.checkers = self.compute_checkers();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirrored() {
+        let chessboard = Chessboard::new("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1".to_string());
+        let mirrored = chessboard.mirrored();
+        assert_eq!(mirrored.to_fen(), "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1");
+        assert!(mirrored.is_mailbox_consistent());
+        assert_eq!(mirrored.hash(), mirrored.compute_hash());
+        assert_eq!(mirrored.mirrored().to_fen(), chessboard.to_fen());
+        assert_eq!(mirrored.mirrored().hash(), chessboard.hash());
+
+        let chessboard = Chessboard::new("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3".to_string());
+        assert_eq!(chessboard.mirrored().to_fen(), "rnbqkbnr/pppp1ppp/8/8/3PpP2/8/PPP1P1PP/RNBQKBNR b KQkq f3 0 3");
+    }
+
+    #[test]
+    fn test_flipped_horizontal() {
+        let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+        let mut chessboard = Chessboard::new(format!("{} 3", fen));
+        let mut flipped = chessboard.flipped_horizontal();
+        assert_eq!(flipped.to_fen(), "8/5p2/4p3/r5PK/k1p3R1/8/1P1P4/8 w - - 0 1");
+        assert!(flipped.is_mailbox_consistent());
+        assert_eq!(flipped.flipped_horizontal().to_fen(), fen);
+        assert_eq!(flipped.perft(), chessboard.perft());
+
+        let chessboard = Chessboard::new("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string());
+        assert_eq!(chessboard.flipped_horizontal().to_fen(), "r2k3r/8/8/8/8/8/8/R2K3R w - - 0 1");
+    }
+}
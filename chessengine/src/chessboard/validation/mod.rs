@@ -0,0 +1,183 @@
+use std::fmt;
+
+use utils::color::Color;
+use utils::file::File;
+use utils::piece::Piece;
+use utils::rank::{Rank, RANKS};
+use utils::square::Square;
+
+use super::Chessboard;
+use super::moves::piece::king;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PositionError {
+    MissingKing(Color),
+    TooManyKings(Color),
+    PawnOnBackRank(Square),
+    // No pawn could just have made a double push over the square.
+    InvalidEnPassant(Square),
+    // No king on the back rank or no rook on the square of the right.
+    InvalidCastlingRight(Color, File),
+    OpponentInCheck,
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::MissingKing(color) =>
+                write!(f, "no king for {}", color.to_char()),
+            PositionError::TooManyKings(color) =>
+                write!(f, "more than one king for {}", color.to_char()),
+            PositionError::PawnOnBackRank(square) =>
+                write!(f, "pawn on back rank at {}", square),
+            PositionError::InvalidEnPassant(square) =>
+                write!(f, "no double push can give en passant square {}", square),
+            PositionError::InvalidCastlingRight(color, file) =>
+                write!(f, "castling right on file {} for {} without king and rook", file.to_char(), color.to_char()),
+            PositionError::OpponentInCheck =>
+                write!(f, "side not to move is in check"),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+impl Chessboard {
+    // Check that the position could be reached in a game, as far as move
+    // generation relies on it. Every problem found is reported.
+    pub fn validate(&self) -> Result<(), Vec<PositionError>> {
+        let mut errors = Vec::new();
+
+        for color in [Color::White, Color::Black] {
+            match self.get_pieces_color(&Piece::King, &color).count_ones() {
+                0 => errors.push(PositionError::MissingKing(color)),
+                1 => (),
+                _ => errors.push(PositionError::TooManyKings(color)),
+            }
+        }
+
+        let mut pawns = self.get_pieces(&Piece::Pawn) & (RANKS[Rank::One as usize] | RANKS[Rank::Eight as usize]);
+        while pawns != 0 {
+            errors.push(PositionError::PawnOnBackRank(Square::from_u32(pawns.trailing_zeros())));
+            pawns &= pawns - 1;
+        }
+
+        if let Some(square) = self.en_passant {
+            if !self.is_valid_en_passant(&square) {
+                errors.push(PositionError::InvalidEnPassant(square));
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let (king_side, queen_side) = self.castling_rights(&color);
+
+            for (file, queen_side) in [(king_side, false), (queen_side, true)] {
+                if let Some(file) = file {
+                    if !self.is_valid_castling_right(&color, file, queen_side) {
+                        errors.push(PositionError::InvalidCastlingRight(color, file));
+                    }
+                }
+            }
+        }
+
+        // Only meaningful once each side has a single king.
+        if errors.is_empty() && self.is_in_check(&self.turn.opposite()) {
+            errors.push(PositionError::OpponentInCheck);
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    // The opponent pawn stands in front of the square, and the square and
+    // the one the pawn came from are empty.
+    fn is_valid_en_passant(&self, square: &Square) -> bool {
+        let (rank, pushed, origin) = match self.turn {
+            Color::White => (Rank::Six, Rank::Five, Rank::Seven),
+            Color::Black => (Rank::Three, Rank::Four, Rank::Two),
+        };
+
+        let pawn = Square::new(square.file, pushed);
+        let empty = square.to_bitboard() | Square::new(square.file, origin).to_bitboard();
+
+        square.rank == rank
+            && self.get_pieces_color(&Piece::Pawn, &self.turn.opposite()) & pawn.to_bitboard() != 0
+            && self.empty_board & empty == empty
+    }
+
+    fn is_valid_castling_right(&self, color: &Color, file: File, queen_side: bool) -> bool {
+        let rank = king::back_rank(color);
+        let king = self.get_pieces_color(&Piece::King, color) & RANKS[rank as usize];
+        let rook = Square::new(file, rank);
+
+        king.count_ones() == 1
+            && self.get_pieces_color(&Piece::Rook, color) & rook.to_bitboard() != 0
+            && (file < File::from_u32(king.trailing_zeros() % 8)) == queen_side
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_validate(fen: &str, expected: Vec<PositionError>) {
+        let chessboard = Chessboard::new(fen.to_string());
+        let expected = match expected.is_empty() {
+            true => Ok(()),
+            false => Err(expected),
+        };
+        assert_eq!(chessboard.validate(), expected, "{}", fen);
+    }
+
+    #[test]
+    fn test_validate_kings() {
+        test_validate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", vec![]);
+        test_validate("8/8/8/8/8/8/8/4K3 w - - 0 1", vec![PositionError::MissingKing(Color::Black)]);
+        test_validate("8/8/8/8/8/8/8/8 w - - 0 1", vec![
+            PositionError::MissingKing(Color::White),
+            PositionError::MissingKing(Color::Black),
+        ]);
+        test_validate("4k3/8/8/8/8/8/8/3KK3 w - - 0 1", vec![PositionError::TooManyKings(Color::White)]);
+    }
+
+    #[test]
+    fn test_validate_pawns() {
+        test_validate("P3k3/8/8/8/8/8/8/4K2p w - - 0 1", vec![
+            PositionError::PawnOnBackRank(Square::from_string("h1")),
+            PositionError::PawnOnBackRank(Square::from_string("a8")),
+        ]);
+    }
+
+    #[test]
+    fn test_validate_en_passant() {
+        test_validate("rnbqkbnr/ppp1pppp/8/3p4/8/8/PPPPPPPP/RNBQKBNR w KQkq d6 0 2", vec![]);
+        test_validate("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", vec![]);
+
+        let square = Square::from_string("d6");
+        // No pawn on d5, wrong side to move, or a piece on d7.
+        test_validate("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq d6 0 2", vec![PositionError::InvalidEnPassant(square)]);
+        test_validate("rnbqkbnr/ppp1pppp/8/3p4/8/8/PPPPPPPP/RNBQKBNR b KQkq d6 0 2", vec![PositionError::InvalidEnPassant(square)]);
+        test_validate("rnbqkbnr/pppnpppp/8/3p4/8/8/PPPPPPPP/RNBQKB1R w KQkq d6 0 2", vec![PositionError::InvalidEnPassant(square)]);
+    }
+
+    #[test]
+    fn test_validate_castling() {
+        test_validate("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", vec![]);
+        test_validate("4k3/8/8/8/8/8/8/R3K2R w KQkq - 0 1", vec![
+            PositionError::InvalidCastlingRight(Color::Black, File::H),
+            PositionError::InvalidCastlingRight(Color::Black, File::A),
+        ]);
+        test_validate("r3k2r/8/8/8/8/8/4K3/R6R w KQ - 0 1", vec![
+            PositionError::InvalidCastlingRight(Color::White, File::H),
+            PositionError::InvalidCastlingRight(Color::White, File::A),
+        ]);
+    }
+
+    #[test]
+    fn test_validate_check() {
+        test_validate("4k3/8/8/8/8/8/8/4K2r b - - 0 1", vec![PositionError::OpponentInCheck]);
+        test_validate("4k3/8/8/8/8/8/8/4K2r w - - 0 1", vec![]);
+    }
+}
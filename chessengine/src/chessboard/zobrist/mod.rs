@@ -0,0 +1,193 @@
+use lazy_static::lazy_static;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use utils::color::Color;
+use utils::file::File;
+use utils::piece::Piece;
+use utils::square::Square;
+
+use super::Chessboard;
+
+// Fixed seed so that keys are identical from one run to another.
+const ZOBRIST_SEED: u64 = 0x5eed_c4e5_5b07_2024;
+
+pub struct ZobristKeys {
+    pieces: [[[u64; 64]; 6]; 2],
+    side: u64,
+    castling: [[u64; 8]; 2],
+    en_passant: [u64; 8],
+}
+
+lazy_static! {
+    static ref KEYS: ZobristKeys = ZobristKeys::new();
+}
+
+impl ZobristKeys {
+    fn new() -> ZobristKeys {
+        let mut rng = StdRng::seed_from_u64(ZOBRIST_SEED);
+        let mut keys = ZobristKeys {
+            pieces: [[[0; 64]; 6]; 2],
+            side: 0,
+            castling: [[0; 8]; 2],
+            en_passant: [0; 8],
+        };
+
+        for color in keys.pieces.iter_mut() {
+            for piece in color.iter_mut() {
+                for key in piece.iter_mut() {
+                    *key = rng.gen();
+                }
+            }
+        }
+
+        keys.side = rng.gen();
+
+        for color in keys.castling.iter_mut() {
+            for key in color.iter_mut() {
+                *key = rng.gen();
+            }
+        }
+
+        for key in keys.en_passant.iter_mut() {
+            *key = rng.gen();
+        }
+
+        keys
+    }
+}
+
+pub fn piece_key(piece: &Piece, color: &Color, square: &Square) -> u64 {
+    KEYS.pieces[color.to_usize()][piece.to_usize()][square.to_u32() as usize]
+}
+
+pub fn side_key() -> u64 {
+    KEYS.side
+}
+
+pub fn castling_key(color: &Color, rook_file: &File) -> u64 {
+    KEYS.castling[color.to_usize()][*rook_file as usize]
+}
+
+pub fn en_passant_key(square: &Square) -> u64 {
+    KEYS.en_passant[square.file as usize]
+}
+
+impl Chessboard {
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    // Part of the key that is not a piece: castling rights and en passant
+    // file. Make/unmake xor it out and back in around any
+    // change of those fields.
+    pub(super) fn state_key(&self) -> u64 {
+        let mut key = 0;
+
+        let rights = [
+            (Color::White, self.white_castle),
+            (Color::Black, self.black_castle),
+        ];
+        for (color, (king_side, queen_side)) in rights.iter() {
+            for file in king_side.iter().chain(queen_side.iter()) {
+                key ^= castling_key(color, file);
+            }
+        }
+
+        // Like the FIDE repetition rule, only count the en passant square
+        // when it can actually be taken.
+        if let Some(square) = self.en_passant {
+            if self.is_en_passant_capturable() {
+                key ^= en_passant_key(&square);
+            }
+        }
+
+        key
+    }
+
+    // Recompute the key from scratch, used to check the incremental update.
+    pub fn compute_hash(&self) -> u64 {
+        let mut key = self.state_key();
+
+        for sq in 0..64 {
+            let square = Square::from_u32(sq);
+            if let (Some(piece), Some(color)) = (self.get_piece(&square), self.get_color(&square)) {
+                key ^= piece_key(&piece, &color, &square);
+            }
+        }
+
+        if self.turn == Color::Black {
+            key ^= side_key();
+        }
+
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessboard::moves::GenType;
+
+    fn play(chessboard: &mut Chessboard, moves: &[&str]) {
+        for mv in moves {
+            let mv = chessboard.generate_move_from_string(mv.to_string());
+            chessboard.make_move(&mv);
+            assert_eq!(chessboard.hash(), chessboard.compute_hash(), "Incremental hash after {}", mv.to_string());
+        }
+    }
+
+    #[test]
+    fn test_hash_from_fen() {
+        let chessboard = Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
+        assert_eq!(chessboard.hash(), chessboard.compute_hash());
+
+        let black = Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1".to_string());
+        assert_eq!(chessboard.hash() ^ side_key(), black.hash());
+
+        let no_castle = Chessboard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1".to_string());
+        assert_ne!(chessboard.hash(), no_castle.hash());
+    }
+
+    #[test]
+    fn test_hash_make_unmake() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut chessboard = Chessboard::new(fen.to_string());
+        let initial = chessboard.hash();
+
+        for mv in chessboard.generate_legal_moves(GenType::All) {
+            chessboard.make_move(&mv);
+            assert_eq!(chessboard.hash(), chessboard.compute_hash(), "Make {}", mv.to_string());
+            chessboard.unmake_move(&mv);
+            assert_eq!(chessboard.hash(), initial, "Unmake {}", mv.to_string());
+        }
+    }
+
+    #[test]
+    fn test_hash_transposition() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let mut first = Chessboard::new(fen.to_string());
+        let mut second = Chessboard::new(fen.to_string());
+
+        play(&mut first, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+        play(&mut second, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+        assert_eq!(first.hash(), second.hash());
+
+        // Knights going back and forth come back to the starting key.
+        let start = Chessboard::new(fen.to_string());
+        let mut chessboard = Chessboard::new(fen.to_string());
+        play(&mut chessboard, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert_eq!(chessboard.hash(), start.hash());
+    }
+
+    #[test]
+    fn test_hash_en_passant_and_castling() {
+        let mut chessboard = Chessboard::new("rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 2".to_string());
+        play(&mut chessboard, &["d5e6"]);
+
+        let mut chessboard = Chessboard::new("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string());
+        play(&mut chessboard, &["e1g1", "e8c8"]);
+
+        let mut chessboard = Chessboard::new("8/P7/8/8/8/8/8/k6K w - - 0 1".to_string());
+        play(&mut chessboard, &["a7a8q"]);
+    }
+}
@@ -15,6 +15,9 @@ fn main() {
         utils::Flag::Perft => {
             chessboard::perft::run_perft(args.get_file_path());
         },
+        utils::Flag::Bench => {
+            chessboard::perft::run_bench();
+        },
         utils::Flag::Invalid => {
             println!("Invalid flag: {}", args.get_file_path());
             utils::print_help();
@@ -12,6 +12,7 @@ pub enum Flag {
     Ai,
     Help,
     Perft,
+    Bench,
     Invalid,
 }
 
@@ -46,6 +47,7 @@ pub fn parse_args(
         Some(arg) => match arg.as_str() {
             "-h" => Args::new(Flag::Help, String::from("")),
             "--perft" => Args::new(Flag::Perft, args.next().unwrap().to_string()),
+            "--bench" => Args::new(Flag::Bench, String::from("")),
             _ => Args::new(Flag::Invalid, String::from(arg)),
         },
         None => Args::new(Flag::Ai, String::from("")),
@@ -58,6 +60,8 @@ pub fn print_help() {
     println!("Flags:");
     println!("  -h: Print this help message.");
     println!("  --perft [depth]: Run a perft test on the given file.");
+    println!("           An .epd file checks the D1 to Dn counts of each line.");
+    println!("  --bench: Measure the move generation speed in nodes per second.");
 }
 
 #[cfg(test)]
@@ -97,6 +101,17 @@ mod tests {
         assert_eq!(parsed_args.get_file_path(), &String::from("test.perft"));
     }
 
+    #[test]
+    fn test_parse_args_bench() {
+        let args = vec![
+            String::from("chessengine"),
+            String::from("--bench"),
+        ];
+        let parsed_args = parse_args(args.into_iter());
+        assert_eq!(parsed_args.get_flag(), &Flag::Bench);
+        assert_eq!(parsed_args.get_file_path(), &String::from(""));
+    }
+
     #[test]
     fn test_parse_args_invalid() {
         let args = vec![